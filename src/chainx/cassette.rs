@@ -0,0 +1,169 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+use std::{
+    collections::HashMap,
+    fs,
+    io::{BufRead, BufReader, ErrorKind, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::chainx::{mock::request_key, types::Hash, RpcRecord};
+
+/// The mode of the rpc cassette.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CassetteMode {
+    /// Send the requests to the node, and write every response into the cassette.
+    Record,
+    /// Serve the requests from the cassette only, without connecting to the node.
+    Replay,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CassetteConfig {
+    /// The directory of the cassette.
+    pub dir: PathBuf,
+    pub mode: CassetteMode,
+}
+
+/// The on-disk cassette of the rpc responses, owned by the transport of a connection.
+///
+/// The records are partitioned by the block hash of requests (the last param), each partition
+/// is a JSON lines file of `RpcRecord` named by the hash, and the requests without a block hash
+/// are kept in `unhashed.jsonl`.
+pub struct Cassette {
+    dir: PathBuf,
+    /// The partitions that have been loaded for replaying.
+    partitions: Mutex<HashMap<String, Arc<HashMap<String, Value>>>>,
+}
+
+impl Cassette {
+    /// Open the cassette of the directory, the directory is created if it doesn't exist.
+    pub fn open<P: AsRef<Path>>(dir: P) -> Result<Self> {
+        let dir = dir.as_ref().to_path_buf();
+        fs::create_dir_all(&dir)?;
+        Ok(Self {
+            dir,
+            partitions: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Append a response into the cassette.
+    pub fn record(&self, method: &str, params: &[Value], result: &Value) -> Result<()> {
+        let record = RpcRecord {
+            method: method.to_string(),
+            params: params.to_vec(),
+            result: result.clone(),
+        };
+        let mut line = serde_json::to_string(&record)?;
+        line.push('\n');
+        // Write the whole line at once, since the file may be appended by other connections.
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.partition_path(params))?;
+        file.write_all(line.as_bytes())?;
+        Ok(())
+    }
+
+    /// Serve a request with the response in the cassette.
+    pub fn replay(&self, method: &str, params: &[Value]) -> Result<Value> {
+        let partition = self.partition(params)?;
+        partition
+            .get(&request_key(method, params))
+            .cloned()
+            .ok_or_else(|| {
+                anyhow!(
+                    "Cassette: no response of `{}` with params {}",
+                    method,
+                    Value::Array(params.to_vec())
+                )
+            })
+    }
+
+    fn partition(&self, params: &[Value]) -> Result<Arc<HashMap<String, Value>>> {
+        let name = partition_name(params);
+        let mut partitions = self
+            .partitions
+            .lock()
+            .expect("partitions lock poisoned; qed");
+        if let Some(partition) = partitions.get(&name) {
+            return Ok(partition.clone());
+        }
+
+        let path = self.partition_path(params);
+        let mut responses = HashMap::new();
+        match fs::File::open(&path) {
+            Ok(file) => {
+                for line in BufReader::new(file).lines() {
+                    let record: RpcRecord = serde_json::from_str(&line?)?;
+                    responses.insert(request_key(&record.method, &record.params), record.result);
+                }
+                log::info!("Cassette {} loaded: {}", path.display(), responses.len());
+            }
+            Err(err) if err.kind() == ErrorKind::NotFound => {}
+            Err(err) => return Err(err.into()),
+        }
+        let partition = Arc::new(responses);
+        partitions.insert(name, partition.clone());
+        Ok(partition)
+    }
+
+    fn partition_path(&self, params: &[Value]) -> PathBuf {
+        self.dir.join(format!("{}.jsonl", partition_name(params)))
+    }
+}
+
+fn partition_name(params: &[Value]) -> String {
+    params
+        .last()
+        .and_then(|param| serde_json::from_value::<Hash>(param.clone()).ok())
+        .map(|hash| format!("{:#x}", hash))
+        .unwrap_or_else(|| "unhashed".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_record_and_replay() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("chainx-cassette-{}", std::process::id()));
+        let hash = json!(format!("{:#x}", Hash::repeat_byte(0x01)));
+
+        let recorder = Cassette::open(&dir)?;
+        recorder.record("chain_getBlockHash", &[json!(1)], &hash)?;
+        recorder.record(
+            "state_getStorage",
+            &[json!("0x1234"), hash.clone()],
+            &json!("0x00"),
+        )?;
+        recorder.record("chainx_getIntentionsV1", &[hash.clone()], &Value::Null)?;
+        assert!(dir.join("unhashed.jsonl").exists());
+        assert!(dir
+            .join(format!("{}.jsonl", hash.as_str().unwrap()))
+            .exists());
+
+        let player = Cassette::open(&dir)?;
+        assert_eq!(player.replay("chain_getBlockHash", &[json!(1)])?, hash);
+        assert_eq!(
+            player.replay("state_getStorage", &[json!("0x1234"), hash.clone()])?,
+            json!("0x00")
+        );
+        assert_eq!(
+            player.replay("chainx_getIntentionsV1", &[hash.clone()])?,
+            Value::Null
+        );
+        assert!(player.replay("chainx_getIntentions", &[hash]).is_err());
+
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
@@ -6,6 +6,7 @@ use std::iter::FromIterator;
 use std::mem;
 
 use codec::{Compact, Decode, Encode, Input, Output};
+use serde::{Deserialize, Serialize};
 use sp_core::H256;
 
 use light_bitcoin::primitives::H264;
@@ -142,7 +143,7 @@ pub enum XAssetsRawEvent<AccountId, Balance, SignedBalance> {
     Change(Token, AccountId, AssetType, SignedBalance),
 }
 
-#[derive(PartialEq, Eq, Clone, Copy, Debug, Encode, Decode)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Encode, Decode, Serialize, Deserialize)]
 pub enum ApplicationState {
     Applying,
     Processing,
@@ -191,17 +192,17 @@ pub type TradeHistoryIndex = u64;
 pub type TradingPairIndex = u32;
 #[derive(PartialEq, Eq, Clone, Debug, Default, Encode, Decode)]
 pub struct CurrencyPair(Token, Token);
-#[derive(PartialEq, Eq, Clone, Copy, Debug, Encode, Decode)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Encode, Decode, Serialize, Deserialize)]
 pub enum OrderType {
     Limit,
     Market,
 }
-#[derive(PartialEq, Eq, Clone, Copy, Debug, Encode, Decode)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Encode, Decode, Serialize, Deserialize)]
 pub enum Side {
     Buy,
     Sell,
 }
-#[derive(PartialEq, Eq, Clone, Copy, Debug, Encode, Decode)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Encode, Decode, Serialize, Deserialize)]
 pub enum OrderStatus {
     ZeroFill,
     ParitialFill,
@@ -220,7 +221,7 @@ pub enum XSpotRawEvent<AccountId, Balance, BlockNumber, Price> {
     PriceVolatility(u32),
 }
 
-#[derive(PartialEq, Eq, Clone, Copy, Debug, Encode, Decode)]
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Encode, Decode, Serialize, Deserialize)]
 pub enum TxState {
     NotApplying,
     Applying,
@@ -285,6 +286,46 @@ pub enum XBridgeFeaturesRawEvent<AccountId> {
 }
 
 type Proposal = Call;
+
+/// The `XMultiSig` address types.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, Encode, Decode, Serialize, Deserialize)]
+pub enum AddrType {
+    Normal,
+    Root,
+    Trustee,
+}
+
+/// The `XMultiSig MultiSigAddrInfo` of a multisig address.
+#[derive(PartialEq, Eq, Clone, Debug, Encode, Decode)]
+pub struct AddrInfo<AccountId> {
+    pub addr_type: AddrType,
+    pub required_num: u32,
+    /// The owners and whether they are the admins.
+    pub owner_list: Vec<(AccountId, bool)>,
+}
+
+/// The `XMultiSig PendingStateFor` of a pending proposal, the proposal is kept as the encoded
+/// call to be decoded by the runtime metadata.
+#[derive(PartialEq, Eq, Clone, Debug)]
+pub struct PendingState {
+    pub yet_needed: u32,
+    pub owners_done: u32,
+    pub proposal: Vec<u8>,
+}
+
+impl PendingState {
+    /// The proposal is the rest of the storage after `yet_needed` and `owners_done`.
+    pub fn decode_raw(mut data: &[u8]) -> Result<Self, codec::Error> {
+        let yet_needed = Decode::decode(&mut data)?;
+        let owners_done = Decode::decode(&mut data)?;
+        Ok(Self {
+            yet_needed,
+            owners_done,
+            proposal: data.to_vec(),
+        })
+    }
+}
+
 pub type XMultisigEvent = XMultisigRawEvent<AccountId, Hash, Proposal>;
 #[derive(Clone, Debug, Eq, PartialEq, Encode, Decode)]
 pub enum XMultisigRawEvent<AccountId, Hash, Proposal> {
@@ -0,0 +1,712 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::{anyhow, Result};
+use codec::{Compact, Decode};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+use crate::chainx::metadata::Metadata;
+
+/// The definition of a type that is referred by name in the metadata.
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize)]
+#[serde(untagged)]
+pub enum TypeDef {
+    /// Another type, like `"Balance": "u64"`.
+    Alias(String),
+    /// A struct with the ordered fields, like `"Revocation": [["block_number", "BlockNumber"],
+    /// ["value", "Balance"]]`.
+    Struct(Vec<(String, String)>),
+    /// An enum with the ordered variants and the types of their fields, like
+    /// `"SignedBalance": { "variants": [["Positive", ["Balance"]], ["Negative", ["Balance"]]] }`.
+    Enum {
+        variants: Vec<(String, Vec<String>)>,
+    },
+}
+
+/// The types of ChainX 1.0 referred in the calls and events, the primitives and the generic
+/// containers (`Vec`, `Option`, `Compact`, tuples and arrays) are built in.
+const CHAINX_TYPES: &[(&str, &str)] = &[
+    ("AccountId", "[u8; 32]"),
+    ("Hash", "[u8; 32]"),
+    ("H256", "[u8; 32]"),
+    ("H264", "[u8; 33]"),
+    ("H520", "[u8; 65]"),
+    ("SessionKey", "[u8; 32]"),
+    ("AuthorityId", "[u8; 32]"),
+    ("EthereumAddress", "[u8; 20]"),
+    ("Signature", "[u8; 64]"),
+    ("Balance", "u64"),
+    ("BlockNumber", "u64"),
+    ("Moment", "u64"),
+    ("Index", "u64"),
+    ("Price", "u64"),
+    ("OrderIndex", "u64"),
+    ("TradeHistoryIndex", "u64"),
+    ("TradingPairIndex", "u32"),
+    ("AccountIndex", "u32"),
+    ("Precision", "u16"),
+    ("Token", "Text"),
+    ("Name", "Text"),
+    ("Memo", "Text"),
+    ("URL", "Text"),
+    ("Desc", "Text"),
+    ("AddrStr", "Text"),
+    ("XString", "Text"),
+    ("Key", "Vec<u8>"),
+    ("KeyValue", "(Vec<u8>, Vec<u8>)"),
+    ("CurrencyPair", "(Token, Token)"),
+    ("Proposal", "Call"),
+    ("BitcoinTrusteeType", "BitcoinPublic"),
+];
+
+/// The enums and structs of ChainX 1.0, as `(name, variants or fields)`.
+const CHAINX_ENUMS: &[(&str, &[(&str, &[&str])])] = &[
+    (
+        "Chain",
+        &[("ChainX", &[]), ("Bitcoin", &[]), ("Ethereum", &[])],
+    ),
+    (
+        "AssetType",
+        &[
+            ("Free", &[]),
+            ("ReservedStaking", &[]),
+            ("ReservedStakingRevocation", &[]),
+            ("ReservedWithdrawal", &[]),
+            ("ReservedDexSpot", &[]),
+            ("ReservedDexFuture", &[]),
+            ("ReservedCurrency", &[]),
+            ("ReservedXRC20", &[]),
+            ("GasPayment", &[]),
+        ],
+    ),
+    (
+        "AssetLimit",
+        &[
+            ("CanMove", &[]),
+            ("CanTransfer", &[]),
+            ("CanDeposit", &[]),
+            ("CanWithdraw", &[]),
+            ("CanDestroyWithdrawal", &[]),
+            ("CanDestroyFree", &[]),
+        ],
+    ),
+    (
+        "SignedBalance",
+        &[("Positive", &["Balance"]), ("Negative", &["Balance"])],
+    ),
+    (
+        "ApplicationState",
+        &[
+            ("Applying", &[]),
+            ("Processing", &[]),
+            ("NormalFinish", &[]),
+            ("RootFinish", &[]),
+            ("NormalCancel", &[]),
+            ("RootCancel", &[]),
+        ],
+    ),
+    ("OrderType", &[("Limit", &[]), ("Market", &[])]),
+    ("Side", &[("Buy", &[]), ("Sell", &[])]),
+    (
+        "OrderStatus",
+        &[
+            ("ZeroFill", &[]),
+            ("ParitialFill", &[]),
+            ("Filled", &[]),
+            ("ParitialFillAndCanceled", &[]),
+            ("Canceled", &[]),
+        ],
+    ),
+    (
+        "TxState",
+        &[
+            ("NotApplying", &[]),
+            ("Applying", &[]),
+            ("Signing", &[]),
+            ("Broadcasting", &[]),
+            ("Processing", &[]),
+            ("Confirming", &["u32", "u32"]),
+            ("Confirmed", &[]),
+            ("Unknown", &[]),
+        ],
+    ),
+    (
+        "BitcoinPublic",
+        &[("Normal", &["H520"]), ("Compressed", &["H264"])],
+    ),
+];
+
+const CHAINX_STRUCTS: &[(&str, &[(&str, &str)])] = &[
+    (
+        "Asset",
+        &[
+            ("token", "Token"),
+            ("token_name", "Token"),
+            ("chain", "Chain"),
+            ("precision", "Precision"),
+            ("desc", "Desc"),
+        ],
+    ),
+    (
+        "TrusteeInfoConfig",
+        &[("min_trustee_count", "u32"), ("max_trustee_count", "u32")],
+    ),
+    (
+        "TrusteeIntentionProps",
+        &[
+            ("about", "Text"),
+            ("hot_entity", "BitcoinPublic"),
+            ("cold_entity", "BitcoinPublic"),
+        ],
+    ),
+    (
+        "BitcoinTrusteeIntentionProps",
+        &[
+            ("about", "Text"),
+            ("hot_entity", "BitcoinPublic"),
+            ("cold_entity", "BitcoinPublic"),
+        ],
+    ),
+    (
+        "TrusteeSessionInfo",
+        &[
+            ("trustee_list", "Vec<AccountId>"),
+            ("hot_address", "BtcTrusteeAddrInfo"),
+            ("cold_address", "BtcTrusteeAddrInfo"),
+        ],
+    ),
+    (
+        "BitcoinTrusteeSessionInfo",
+        &[
+            ("trustee_list", "Vec<AccountId>"),
+            ("hot_address", "BtcTrusteeAddrInfo"),
+            ("cold_address", "BtcTrusteeAddrInfo"),
+        ],
+    ),
+    (
+        "BtcTrusteeAddrInfo",
+        &[("addr", "BitcoinAddress"), ("redeem_script", "Vec<u8>")],
+    ),
+    (
+        "BitcoinAddress",
+        &[("kind", "u8"), ("network", "u8"), ("hash", "[u8; 20]")],
+    ),
+];
+
+/// The registry of the named types, which decodes the SCALE data of any type in the metadata
+/// into a JSON value.
+#[derive(Clone, Debug)]
+pub struct TypeRegistry {
+    types: HashMap<String, TypeDef>,
+}
+
+impl Default for TypeRegistry {
+    fn default() -> Self {
+        let mut types = HashMap::new();
+        for (name, ty) in CHAINX_TYPES {
+            types.insert(name.to_string(), TypeDef::Alias(ty.to_string()));
+        }
+        for (name, variants) in CHAINX_ENUMS {
+            let variants = variants
+                .iter()
+                .map(|(variant, fields)| {
+                    let fields = fields.iter().map(|field| field.to_string()).collect();
+                    (variant.to_string(), fields)
+                })
+                .collect();
+            types.insert(name.to_string(), TypeDef::Enum { variants });
+        }
+        for (name, fields) in CHAINX_STRUCTS {
+            let fields = fields
+                .iter()
+                .map(|(field, ty)| (field.to_string(), ty.to_string()))
+                .collect();
+            types.insert(name.to_string(), TypeDef::Struct(fields));
+        }
+        Self { types }
+    }
+}
+
+impl TypeRegistry {
+    /// The ChainX 1.0 types, extended (or overridden) by the given types.
+    pub fn with_types(types: &BTreeMap<String, TypeDef>) -> Self {
+        let mut registry = Self::default();
+        for (name, ty) in types {
+            registry.types.insert(name.clone(), ty.clone());
+        }
+        registry
+    }
+
+    /// Decode a value of the type, the calls (`Call`) are decoded with the metadata.
+    pub fn decode(&self, metadata: &Metadata, ty: &str, input: &mut &[u8]) -> Result<Value> {
+        let ty = normalize(ty);
+        self.decode_type(metadata, &ty, input)
+            .map_err(|err| anyhow!("`{}`: {}", ty, err))
+    }
+
+    /// Decode an event record: `{ phase, module, event, args, topics }`, the topics are empty if
+    /// the records of the runtime have no topics.
+    pub fn decode_event_record(
+        &self,
+        metadata: &Metadata,
+        input: &mut &[u8],
+        topics: bool,
+    ) -> Result<Value> {
+        let phase = match decode::<u8>(input)? {
+            0 => json!({ "applyExtrinsic": decode::<u32>(input)? }),
+            1 => json!("finalization"),
+            phase => return Err(anyhow!("Unknown phase: {}", phase)),
+        };
+        let (module_index, event_index) = (decode::<u8>(input)?, decode::<u8>(input)?);
+        let (module, event) = metadata
+            .event(module_index, event_index)
+            .ok_or_else(|| anyhow!("Unknown event: ({}, {})", module_index, event_index))?;
+        let mut args = vec![];
+        for ty in &event.arguments {
+            args.push(self.decode(metadata, ty, input)?);
+        }
+        let topics = if topics {
+            self.decode(metadata, "Vec<Hash>", input)?
+        } else {
+            json!([])
+        };
+        Ok(json!({
+            "phase": phase,
+            "module": module.name,
+            "event": event.name,
+            "args": args,
+            "topics": topics,
+        }))
+    }
+
+    /// Decode the event records of a block, which is the `System Events` storage.
+    ///
+    /// The whole storage must be consumed, otherwise the records are of another layout.
+    pub fn decode_events(
+        &self,
+        metadata: &Metadata,
+        data: &[u8],
+        topics: bool,
+    ) -> Result<Vec<Value>> {
+        let input = &mut &data[..];
+        let count = decode::<Compact<u32>>(input)?.0;
+        let mut records = Vec::with_capacity(count as usize);
+        for index in 0..count {
+            let record = self
+                .decode_event_record(metadata, input, topics)
+                .map_err(|err| anyhow!("Event #{}: {}", index, err))?;
+            records.push(record);
+        }
+        if !input.is_empty() {
+            return Err(anyhow!(
+                "{} trailing bytes after the event records",
+                input.len()
+            ));
+        }
+        Ok(records)
+    }
+
+    /// Decode a call: `{ module, call, args: { name: value } }`.
+    pub fn decode_call(&self, metadata: &Metadata, input: &mut &[u8]) -> Result<Value> {
+        let (module_index, call_index) = (decode::<u8>(input)?, decode::<u8>(input)?);
+        let (module, call) = metadata
+            .call(module_index, call_index)
+            .ok_or_else(|| anyhow!("Unknown call: ({}, {})", module_index, call_index))?;
+        let mut args = Map::new();
+        for (name, ty) in &call.arguments {
+            args.insert(name.clone(), self.decode(metadata, ty, input)?);
+        }
+        Ok(json!({
+            "module": module.name,
+            "call": call.name,
+            "args": args,
+        }))
+    }
+
+    /// The type an alias refers to in the end, other types are returned as they are.
+    fn resolve_alias(&self, ty: &str) -> String {
+        let mut ty = ty.to_string();
+        // Bounded in case of the cyclic aliases of the given types.
+        for _ in 0..16 {
+            match self.types.get(ty.split('<').next().unwrap_or(&ty)) {
+                Some(TypeDef::Alias(alias)) => ty = normalize(alias),
+                _ => break,
+            }
+        }
+        ty
+    }
+
+    fn decode_type(&self, metadata: &Metadata, ty: &str, input: &mut &[u8]) -> Result<Value> {
+        if let Some(inner) = generic(ty, "Vec") {
+            let len = decode::<Compact<u32>>(input)?.0;
+            if inner == "u8" {
+                return Ok(hex_value(take(input, len as usize)?));
+            }
+            let mut values = Vec::with_capacity(len as usize);
+            for _ in 0..len {
+                values.push(self.decode_type(metadata, inner, input)?);
+            }
+            return Ok(Value::Array(values));
+        }
+        if let Some(inner) = generic(ty, "Option") {
+            // `Option<bool>` is encoded in a single byte.
+            if inner == "bool" {
+                return match decode::<u8>(input)? {
+                    0 => Ok(Value::Null),
+                    1 => Ok(Value::Bool(true)),
+                    2 => Ok(Value::Bool(false)),
+                    byte => Err(anyhow!("Invalid Option<bool>: {}", byte)),
+                };
+            }
+            return match decode::<u8>(input)? {
+                0 => Ok(Value::Null),
+                1 => self.decode_type(metadata, inner, input),
+                byte => Err(anyhow!("Invalid Option: {}", byte)),
+            };
+        }
+        if let Some(inner) = generic(ty, "Compact") {
+            // The compact aliases like `Compact<Balance>` are resolved to their integer types,
+            // the unknown ones are kept in strings as `u128`, so that nothing is truncated.
+            return match self.resolve_alias(inner).as_str() {
+                "u8" | "u16" | "u32" | "u64" => Ok(json!(decode::<Compact<u64>>(input)?.0)),
+                _ => Ok(json!(decode::<Compact<u128>>(input)?.0.to_string())),
+            };
+        }
+        if let Some(inner) = generic(ty, "Box") {
+            return self.decode_type(metadata, inner, input);
+        }
+        if let Some(inner) = generic(ty, "BTreeMap") {
+            // The maps of ChainX 1.0 are encoded with the `u32` length.
+            let (key, value) = match split_top_level(inner).as_slice() {
+                [key, value] => (key.to_string(), value.to_string()),
+                _ => return Err(anyhow!("Invalid BTreeMap")),
+            };
+            let len = decode::<u32>(input)?;
+            let mut values = Vec::with_capacity(len as usize);
+            for _ in 0..len {
+                let key = self.decode_type(metadata, &key, input)?;
+                let value = self.decode_type(metadata, &value, input)?;
+                values.push(json!([key, value]));
+            }
+            return Ok(Value::Array(values));
+        }
+        if ty.starts_with('(') && ty.ends_with(')') {
+            let mut values = vec![];
+            for element in split_top_level(&ty[1..ty.len() - 1]) {
+                values.push(self.decode_type(metadata, element, input)?);
+            }
+            return Ok(if values.is_empty() {
+                Value::Null
+            } else {
+                Value::Array(values)
+            });
+        }
+        if ty.starts_with('[') && ty.ends_with(']') {
+            let (inner, len) = ty[1..ty.len() - 1]
+                .rsplit_once(';')
+                .ok_or_else(|| anyhow!("Invalid array"))?;
+            let len = len.parse::<usize>()?;
+            if inner == "u8" {
+                return Ok(hex_value(take(input, len)?));
+            }
+            let mut values = Vec::with_capacity(len);
+            for _ in 0..len {
+                values.push(self.decode_type(metadata, inner, input)?);
+            }
+            return Ok(Value::Array(values));
+        }
+
+        match ty {
+            "bool" => return Ok(json!(decode::<bool>(input)?)),
+            "u8" => return Ok(json!(decode::<u8>(input)?)),
+            "u16" => return Ok(json!(decode::<u16>(input)?)),
+            "u32" => return Ok(json!(decode::<u32>(input)?)),
+            "u64" => return Ok(json!(decode::<u64>(input)?)),
+            "u128" => return Ok(json!(decode::<u128>(input)?.to_string())),
+            "i8" => return Ok(json!(decode::<i8>(input)?)),
+            "i16" => return Ok(json!(decode::<i16>(input)?)),
+            "i32" => return Ok(json!(decode::<i32>(input)?)),
+            "i64" => return Ok(json!(decode::<i64>(input)?)),
+            "i128" => return Ok(json!(decode::<i128>(input)?.to_string())),
+            "Text" => {
+                let bytes = decode::<Vec<u8>>(input)?;
+                return Ok(match String::from_utf8(bytes) {
+                    Ok(text) => Value::String(text),
+                    Err(err) => hex_value(err.as_bytes()),
+                });
+            }
+            "Call" => return self.decode_call(metadata, input),
+            "Address" | "Source" | "LookupSource" => return decode_address(input),
+            _ => {}
+        }
+
+        // The generic parameters of the named types are ignored, like `SignedBalance<T>`.
+        let name = ty.split('<').next().unwrap_or(ty);
+        match self.types.get(name) {
+            Some(TypeDef::Alias(alias)) => self.decode_type(metadata, &normalize(alias), input),
+            Some(TypeDef::Struct(fields)) => {
+                let mut values = Map::new();
+                for (field, field_ty) in fields {
+                    let value = self.decode_type(metadata, &normalize(field_ty), input)?;
+                    values.insert(field.clone(), value);
+                }
+                Ok(Value::Object(values))
+            }
+            Some(TypeDef::Enum { variants }) => {
+                let index = decode::<u8>(input)?;
+                let (variant, fields) = variants
+                    .get(index as usize)
+                    .ok_or_else(|| anyhow!("Unknown variant {} of `{}`", index, name))?;
+                if fields.is_empty() {
+                    return Ok(Value::String(variant.clone()));
+                }
+                let mut values = vec![];
+                for field_ty in fields {
+                    values.push(self.decode_type(metadata, &normalize(field_ty), input)?);
+                }
+                let mut value = Map::new();
+                value.insert(variant.clone(), Value::Array(values));
+                Ok(Value::Object(value))
+            }
+            None => Err(anyhow!("Unknown type `{}`", ty)),
+        }
+    }
+}
+
+fn decode<T: Decode>(input: &mut &[u8]) -> Result<T> {
+    Ok(T::decode(input)?)
+}
+
+fn take<'a>(input: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+    if input.len() < len {
+        return Err(anyhow!("Not enough data"));
+    }
+    let (bytes, rest) = input.split_at(len);
+    *input = rest;
+    Ok(bytes)
+}
+
+fn hex_value(bytes: &[u8]) -> Value {
+    Value::String(format!("0x{}", hex::encode(bytes)))
+}
+
+/// The `Address` of the indices module: an account id, or an account index in 1, 2, 4 or 8
+/// bytes.
+fn decode_address(input: &mut &[u8]) -> Result<Value> {
+    Ok(match decode::<u8>(input)? {
+        0xff => json!({ "id": hex_value(take(input, 32)?) }),
+        0xfc => json!({ "index": decode::<u16>(input)? }),
+        0xfd => json!({ "index": decode::<u32>(input)? }),
+        0xfe => json!({ "index": decode::<u64>(input)? }),
+        index if index < 0xf0 => json!({ "index": index }),
+        byte => return Err(anyhow!("Invalid address: {:#x}", byte)),
+    })
+}
+
+/// Remove the whitespaces and the trait paths, like `<T as Trait>::Balance` and `T::Balance`
+/// are both `Balance`.
+pub(crate) fn normalize(ty: &str) -> String {
+    let mut ty = ty
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>();
+    while let Some(start) = ty.find("<T") {
+        match ty[start..].find(">::") {
+            Some(end) if !ty[start + 1..start + end].contains('<') => {
+                ty.replace_range(start..start + end + 3, "");
+            }
+            _ => break,
+        }
+    }
+    ty.replace("T::", "")
+}
+
+/// The parameter of a generic type, like `u8` of `Vec<u8>`.
+fn generic<'a>(ty: &'a str, name: &str) -> Option<&'a str> {
+    ty.strip_prefix(name)?.strip_prefix('<')?.strip_suffix('>')
+}
+
+/// Split the elements of a tuple or the parameters of a generic type by the top-level commas.
+fn split_top_level(types: &str) -> Vec<&str> {
+    let mut elements = vec![];
+    let (mut depth, mut start) = (0, 0);
+    for (index, c) in types.char_indices() {
+        match c {
+            '<' | '(' | '[' => depth += 1,
+            '>' | ')' | ']' => depth -= 1,
+            ',' if depth == 0 => {
+                elements.push(&types[start..index]);
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    if start < types.len() {
+        elements.push(&types[start..]);
+    }
+    elements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chainx::metadata::{EventMetadata, FunctionMetadata, ModuleMetadata};
+    use codec::Encode;
+
+    fn metadata() -> Metadata {
+        let event = |name: &str, arguments: &[&str]| EventMetadata {
+            name: name.to_string(),
+            arguments: arguments.iter().map(|ty| ty.to_string()).collect(),
+        };
+        Metadata {
+            version: 3,
+            modules: vec![
+                ModuleMetadata {
+                    name: "System".to_string(),
+                    calls: None,
+                    events: Some(vec![
+                        event("ExtrinsicSuccess", &[]),
+                        event("ExtrinsicFailed", &[]),
+                    ]),
+                },
+                ModuleMetadata {
+                    name: "XAssets".to_string(),
+                    calls: Some(vec![FunctionMetadata {
+                        name: "transfer".to_string(),
+                        arguments: vec![
+                            (
+                                "dest".to_string(),
+                                "<T::Lookup as StaticLookup>::Source".to_string(),
+                            ),
+                            ("token".to_string(), "Token".to_string()),
+                            ("value".to_string(), "T::Balance".to_string()),
+                            ("memo".to_string(), "Memo".to_string()),
+                        ],
+                    }]),
+                    events: Some(vec![
+                        event("NewAccount", &["AccountId"]),
+                        event(
+                            "Change",
+                            &["Token", "AccountId", "AssetType", "SignedBalance<T>"],
+                        ),
+                    ]),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("<T as Trait>::Balance"), "Balance");
+        assert_eq!(normalize("<T::Lookup as StaticLookup>::Source"), "Source");
+        assert_eq!(
+            normalize("Vec<(T::AccountId, u32)>"),
+            "Vec<(AccountId,u32)>"
+        );
+        assert_eq!(
+            split_top_level("Token,Option<(u32,u64)>,[u8;20]"),
+            vec!["Token", "Option<(u32,u64)>", "[u8;20]"]
+        );
+    }
+
+    #[test]
+    fn test_decode_compact() -> Result<()> {
+        let metadata = metadata();
+        let mut types = BTreeMap::new();
+        types.insert("Weight".to_string(), TypeDef::Alias("u128".to_string()));
+        types.insert(
+            "VoteWeight".to_string(),
+            TypeDef::Alias("Weight".to_string()),
+        );
+        let registry = TypeRegistry::with_types(&types);
+
+        let data = Compact(5u64).encode();
+        let value = registry.decode(&metadata, "Compact<T::Balance>", &mut &data[..])?;
+        assert_eq!(value, json!(5));
+
+        let large = u64::MAX as u128 + 1;
+        let data = Compact(large).encode();
+        let value = registry.decode(&metadata, "Compact<VoteWeight>", &mut &data[..])?;
+        assert_eq!(value, json!(large.to_string()));
+        let value = registry.decode(&metadata, "Compact<Unknown>", &mut &data[..])?;
+        assert_eq!(value, json!(large.to_string()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_events() -> Result<()> {
+        let metadata = metadata();
+        let registry = TypeRegistry::default();
+
+        let mut data = codec::Compact(2u32).encode();
+        // [ApplyExtrinsic(1), XAssets::NewAccount(0x11...), no topics]
+        (0u8, 1u32, 1u8, 0u8, [0x11u8; 32], 0u8).encode_to(&mut data);
+        // [Finalization, XAssets::Change(BTC, 0x12..., Free, Negative(5)), no topics]
+        (1u8, 1u8, 1u8, b"BTC".to_vec(), [0x12u8; 32]).encode_to(&mut data);
+        (0u8, 1u8, 5u64, 0u8).encode_to(&mut data);
+
+        let records = registry.decode_events(&metadata, &data, true)?;
+        assert_eq!(
+            records,
+            vec![
+                json!({
+                    "phase": { "applyExtrinsic": 1 },
+                    "module": "XAssets",
+                    "event": "NewAccount",
+                    "args": [format!("0x{}", "11".repeat(32))],
+                    "topics": [],
+                }),
+                json!({
+                    "phase": "finalization",
+                    "module": "XAssets",
+                    "event": "Change",
+                    "args": ["BTC", format!("0x{}", "12".repeat(32)), "Free", { "Negative": [5] }],
+                    "topics": [],
+                }),
+            ]
+        );
+
+        // The runtimes without topics: [ApplyExtrinsic(2), XAssets::NewAccount(0x13...)]
+        let mut data = codec::Compact(1u32).encode();
+        (0u8, 2u32, 1u8, 0u8, [0x13u8; 32]).encode_to(&mut data);
+        let records = registry.decode_events(&metadata, &data, false)?;
+        assert_eq!(
+            records,
+            vec![json!({
+                "phase": { "applyExtrinsic": 2 },
+                "module": "XAssets",
+                "event": "NewAccount",
+                "args": [format!("0x{}", "13".repeat(32))],
+                "topics": [],
+            })]
+        );
+        // The topics are missing, or the trailing byte is left.
+        assert!(registry.decode_events(&metadata, &data, true).is_err());
+        data.push(0u8);
+        assert!(registry.decode_events(&metadata, &data, false).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_call() -> Result<()> {
+        let metadata = metadata();
+        let mut types = BTreeMap::new();
+        types.insert("Memo".to_string(), TypeDef::Alias("Vec<u8>".to_string()));
+        let registry = TypeRegistry::with_types(&types);
+
+        let mut data = vec![0u8, 0u8];
+        (0xfdu8, 7u32, b"PCX".to_vec(), 100u64, vec![0xffu8]).encode_to(&mut data);
+        let call = registry.decode_call(&metadata, &mut &data[..])?;
+        assert_eq!(
+            call,
+            json!({
+                "module": "XAssets",
+                "call": "transfer",
+                "args": { "dest": { "index": 7 }, "token": "PCX", "value": 100, "memo": "0xff" },
+            })
+        );
+        Ok(())
+    }
+}
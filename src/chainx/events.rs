@@ -0,0 +1,524 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+use std::{collections::BTreeMap, fmt};
+
+use anyhow::anyhow;
+use codec::{Compact, Decode, Encode};
+use serde_json::Value;
+
+use crate::chainx::{
+    decode::*,
+    dynamic::{normalize, TypeRegistry},
+    metadata::{EventMetadata, Metadata},
+    types::{AccountIndex, *},
+};
+
+pub type ChainXEventRecord = EventRecord<ChainXEvent, Hash>;
+
+/// The event records decoded from the `System Events` storage.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodedEvents {
+    pub records: Vec<ChainXEventRecord>,
+    /// The indices of the events that were skipped by the metadata, since they are unknown to
+    /// the current runtime.
+    pub skipped: Vec<u32>,
+}
+
+impl From<Vec<ChainXEventRecord>> for DecodedEvents {
+    fn from(records: Vec<ChainXEventRecord>) -> Self {
+        Self {
+            records,
+            skipped: vec![],
+        }
+    }
+}
+
+/// The decoders of the `System Events` storage, which are tried in order until one succeeds.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EventsDecoder {
+    /// The event records of the current runtime.
+    Current,
+    /// The event records without topics, the layout before the topics were introduced.
+    WithoutTopics,
+}
+
+/// Record of an event happening, without the topics.
+#[derive(PartialEq, Eq, Clone, Debug, Encode, Decode)]
+struct EventRecordWithoutTopics<E> {
+    phase: Phase,
+    event: E,
+}
+
+/// The record layouts of the runtimes, keyed by the first spec version of the layout.
+///
+/// The events of the historical runtimes are decoded by the metadata of their runtimes (see
+/// `decode_events_by_metadata`), so only the layout of the records (with or without the topics)
+/// is picked here, the other layouts are tried as the alternatives.
+const SPEC_VERSION_DECODERS: &[(u32, &[EventsDecoder])] = &[(0, &[EventsDecoder::Current])];
+
+impl EventsDecoder {
+    pub const ALL: &'static [EventsDecoder] =
+        &[EventsDecoder::Current, EventsDecoder::WithoutTopics];
+
+    /// The decoders of the event layout that was live in the runtime of the spec version.
+    pub fn of_spec_version(spec_version: u32) -> &'static [EventsDecoder] {
+        SPEC_VERSION_DECODERS
+            .iter()
+            .rev()
+            .find(|(first, _)| *first <= spec_version)
+            .map(|(_, decoders)| *decoders)
+            .unwrap_or(Self::ALL)
+    }
+
+    /// The decoders of the spec version, followed by all other decoders as the alternatives.
+    pub fn with_alternatives(spec_version: u32) -> Vec<EventsDecoder> {
+        let mut decoders = Self::of_spec_version(spec_version).to_vec();
+        for decoder in Self::ALL {
+            if !decoders.contains(decoder) {
+                decoders.push(*decoder);
+            }
+        }
+        decoders
+    }
+
+    /// Whether the event records of the layout have the topics.
+    pub fn has_topics(self) -> bool {
+        self != EventsDecoder::WithoutTopics
+    }
+
+    pub fn decode(self, data: &[u8]) -> Result<DecodedEvents, codec::Error> {
+        match self {
+            EventsDecoder::Current => {
+                let records: Vec<ChainXEventRecord> = Decode::decode(&mut &data[..])?;
+                Ok(records.into())
+            }
+            EventsDecoder::WithoutTopics => {
+                let records: Vec<EventRecordWithoutTopics<ChainXEvent>> = decode_all(data)?;
+                Ok(records
+                    .into_iter()
+                    .map(|record| EventRecord {
+                        phase: record.phase,
+                        event: record.event,
+                        topics: vec![],
+                    })
+                    .collect::<Vec<_>>()
+                    .into())
+            }
+        }
+    }
+}
+
+/// The alternative decoders must consume the whole input, otherwise a different layout may be
+/// decoded by accident.
+fn decode_all<T: Decode>(data: &[u8]) -> Result<T, codec::Error> {
+    let mut input = data;
+    let value = T::decode(&mut input)?;
+    if input.is_empty() {
+        Ok(value)
+    } else {
+        Err("Trailing bytes after the event records".into())
+    }
+}
+
+/// The errors of all the decoders that have been tried.
+#[derive(Debug)]
+pub struct DecodeEventsError(pub Vec<(EventsDecoder, codec::Error)>);
+
+impl fmt::Display for DecodeEventsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (index, (decoder, err)) in self.0.iter().enumerate() {
+            if index > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{:?}: {}", decoder, err)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for DecodeEventsError {}
+
+/// Decode the `System Events` storage with the decoders in order, returns the first decoded
+/// event records and the decoder which decoded them.
+pub fn decode_events(
+    decoders: &[EventsDecoder],
+    data: &[u8],
+) -> Result<(EventsDecoder, DecodedEvents), DecodeEventsError> {
+    let mut errors = vec![];
+    for decoder in decoders {
+        match decoder.decode(data) {
+            Ok(events) => return Ok((*decoder, events)),
+            Err(err) => errors.push((*decoder, err)),
+        }
+    }
+    Err(DecodeEventsError(errors))
+}
+
+/// Decode the `System Events` storage by the metadata of its runtime into JSON values, with the
+/// record layouts of the decoders in order like `decode_events`.
+pub fn decode_dynamic_events(
+    registry: &TypeRegistry,
+    metadata: &Metadata,
+    decoders: &[EventsDecoder],
+    data: &[u8],
+) -> anyhow::Result<(EventsDecoder, Vec<Value>)> {
+    let mut errors = vec![];
+    for decoder in decoders {
+        match registry.decode_events(metadata, data, decoder.has_topics()) {
+            Ok(events) => return Ok((*decoder, events)),
+            Err(err) => errors.push(format!("{:?}: {}", decoder, err)),
+        }
+    }
+    Err(anyhow!("{}", errors.join("; ")))
+}
+
+/// Decode the `System Events` storage by the metadata of its runtime into the `ChainXEvent`s,
+/// with the record layouts of the decoders in order like `decode_events`.
+///
+/// The metadata finds the exact boundary of every event, then each event is mapped by its name
+/// to the indices of the `current` runtime (the `ChainXEvent` layout) and decoded. The events
+/// that are unknown to the current runtime or whose arguments differ from it are skipped.
+pub fn decode_events_by_metadata(
+    registry: &TypeRegistry,
+    metadata: &Metadata,
+    current: &Metadata,
+    decoders: &[EventsDecoder],
+    data: &[u8],
+) -> anyhow::Result<(EventsDecoder, DecodedEvents)> {
+    let mut errors = vec![];
+    for decoder in decoders {
+        match split_events(registry, metadata, decoder.has_topics(), data) {
+            Ok(events) => {
+                let mut decoded = DecodedEvents::from(vec![]);
+                for (index, raw) in events.into_iter().enumerate() {
+                    match decode_current_record(metadata, current, decoder.has_topics(), raw) {
+                        Some(record) => decoded.records.push(record),
+                        None => decoded.skipped.push(index as u32),
+                    }
+                }
+                return Ok((*decoder, decoded));
+            }
+            Err(err) => errors.push(format!("{:?}: {}", decoder, err)),
+        }
+    }
+    Err(anyhow!("{}", errors.join("; ")))
+}
+
+/// Split the `System Events` storage into the raw event records by the metadata.
+fn split_events<'a>(
+    registry: &TypeRegistry,
+    metadata: &Metadata,
+    topics: bool,
+    data: &'a [u8],
+) -> anyhow::Result<Vec<&'a [u8]>> {
+    let mut input = data;
+    let count = <Compact<u32>>::decode(&mut input)
+        .map_err(|err| anyhow!("Invalid number of events: {}", err))?
+        .0;
+    let mut events = Vec::with_capacity(count as usize);
+    for index in 0..count {
+        let begin = input;
+        registry
+            .decode_event_record(metadata, &mut input, topics)
+            .map_err(|err| anyhow!("Event #{}: {}", index, err))?;
+        events.push(&begin[..begin.len() - input.len()]);
+    }
+    if !input.is_empty() {
+        return Err(anyhow!(
+            "{} trailing bytes after the event records",
+            input.len()
+        ));
+    }
+    Ok(events)
+}
+
+/// Decode a raw event record of the runtime into the `ChainXEvent`, after its module and event
+/// indices are replaced by the ones of the current runtime.
+///
+/// The arguments are only decoded if their types are the same as the current runtime, and must
+/// be consumed exactly.
+fn decode_current_record(
+    metadata: &Metadata,
+    current: &Metadata,
+    topics: bool,
+    raw: &[u8],
+) -> Option<ChainXEventRecord> {
+    // `ApplyExtrinsic(u32)` or `Finalization`
+    let phase_len = if raw[0] == 0 { 5 } else { 1 };
+    let (module, event) = metadata.event(raw[phase_len], raw[phase_len + 1])?;
+    let (module_index, event_index) = current.event_index(&module.name, &event.name)?;
+    let (_, current_event) = current.event(module_index, event_index)?;
+    let arguments = |event: &EventMetadata| {
+        event
+            .arguments
+            .iter()
+            .map(|ty| normalize(ty))
+            .collect::<Vec<_>>()
+    };
+    if arguments(event) != arguments(current_event) {
+        return None;
+    }
+
+    let mut record = raw[..phase_len].to_vec();
+    record.extend_from_slice(&[module_index, event_index]);
+    record.extend_from_slice(&raw[phase_len + 2..]);
+    if !topics {
+        // The empty topics
+        record.push(0);
+    }
+    decode_all(&record).ok()
+}
+
+/// The accounts created by the `XAssets::NewAccount` events.
+pub fn new_accounts(records: &[ChainXEventRecord]) -> Vec<AccountId> {
+    records
+        .iter()
+        .filter_map(|record| match &record.event {
+            ChainXEvent::XAssets(XAssetsEvent::NewAccount(account)) => Some(*account),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The accounts created in the block, by the `XAssets::NewAccount` and the
+/// `Indices::NewAccountIndex` events, so an account is still found if one of its events is
+/// skipped.
+pub fn created_accounts(records: &[ChainXEventRecord]) -> Vec<AccountId> {
+    let mut accounts = new_accounts(records);
+    accounts.extend(
+        new_account_indices(records)
+            .into_iter()
+            .map(|(account, _)| account),
+    );
+    accounts.sort_unstable();
+    accounts.dedup();
+    accounts
+}
+
+/// The results of the extrinsics by their indices, from the `System::ExtrinsicSuccess` and
+/// `System::ExtrinsicFailed` events.
+pub fn extrinsic_results(records: &[ChainXEventRecord]) -> BTreeMap<u32, bool> {
+    records
+        .iter()
+        .filter_map(|record| match (&record.phase, &record.event) {
+            (Phase::ApplyExtrinsic(index), ChainXEvent::System(SystemEvent::ExtrinsicSuccess)) => {
+                Some((*index, true))
+            }
+            (Phase::ApplyExtrinsic(index), ChainXEvent::System(SystemEvent::ExtrinsicFailed)) => {
+                Some((*index, false))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// The account indices created by the `Indices::NewAccountIndex` events.
+pub fn new_account_indices(records: &[ChainXEventRecord]) -> Vec<(AccountId, AccountIndex)> {
+    records
+        .iter()
+        .filter_map(|record| match &record.event {
+            ChainXEvent::Indices(IndicesEvent::NewAccountIndex(account, index)) => {
+                Some((*account, *index))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// The modules of the current runtime up to `XAssets`, in the order of `ChainXEvent`.
+#[cfg(test)]
+pub(crate) fn current_metadata() -> Metadata {
+    Metadata::with_events(&[
+        (
+            "System",
+            &[("ExtrinsicSuccess", &[]), ("ExtrinsicFailed", &[])],
+        ),
+        (
+            "Indices",
+            &[("NewAccountIndex", &["AccountId", "AccountIndex"])],
+        ),
+        ("XSession", &[("NewSession", &["BlockNumber"])]),
+        (
+            "XGrandpa",
+            &[("NewAuthorities", &["Vec<(SessionKey, u64)>"])],
+        ),
+        (
+            "XFeeManager",
+            &[
+                ("FeeForJackpot", &["AccountId", "Balance"]),
+                ("FeeForProducer", &["AccountId", "Balance"]),
+                ("FeeForCouncil", &["AccountId", "Balance"]),
+            ],
+        ),
+        (
+            "XAssets",
+            &[
+                (
+                    "Move",
+                    &[
+                        "Token",
+                        "AccountId",
+                        "AssetType",
+                        "AccountId",
+                        "AssetType",
+                        "Balance",
+                    ],
+                ),
+                ("Issue", &["Token", "AccountId", "Balance"]),
+                ("Destory", &["Token", "AccountId", "Balance"]),
+                ("Set", &["Token", "AccountId", "AssetType", "Balance"]),
+                ("Register", &["Token", "bool"]),
+                ("Revoke", &["Token"]),
+                ("NewAccount", &["AccountId"]),
+                (
+                    "Change",
+                    &["Token", "AccountId", "AssetType", "SignedBalance"],
+                ),
+            ],
+        ),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new_account_event(byte: u8) -> ChainXEvent {
+        ChainXEvent::XAssets(XAssetsEvent::NewAccount(AccountId::repeat_byte(byte)))
+    }
+
+    #[test]
+    fn test_decode_events() {
+        let records = vec![
+            EventRecord {
+                phase: Phase::ApplyExtrinsic(1),
+                event: new_account_event(0x01),
+                topics: Vec::<Hash>::new(),
+            },
+            EventRecord {
+                phase: Phase::Finalization,
+                event: ChainXEvent::System(SystemEvent::ExtrinsicSuccess),
+                topics: vec![],
+            },
+        ];
+        let (decoder, decoded) = decode_events(EventsDecoder::ALL, &records.encode()).unwrap();
+        assert_eq!(decoder, EventsDecoder::Current);
+        assert_eq!(decoded, DecodedEvents::from(records));
+        assert_eq!(
+            new_accounts(&decoded.records),
+            vec![AccountId::repeat_byte(0x01)]
+        );
+
+        // [ApplyExtrinsic(1), XAssets::NewAccount(0x02...)] without topics can't be decoded
+        // with the current layout, since the topics are missing.
+        let legacy = vec![EventRecordWithoutTopics {
+            phase: Phase::ApplyExtrinsic(1),
+            event: new_account_event(0x02),
+        }];
+        let (decoder, decoded) = decode_events(EventsDecoder::ALL, &legacy.encode()).unwrap();
+        assert_eq!(decoder, EventsDecoder::WithoutTopics);
+        assert_eq!(
+            new_accounts(&decoded.records),
+            vec![AccountId::repeat_byte(0x02)]
+        );
+
+        // The truncated length of records
+        let err = decode_events(EventsDecoder::ALL, &[0xff]).unwrap_err();
+        assert_eq!(err.0.len(), EventsDecoder::ALL.len());
+    }
+
+    #[test]
+    fn test_decode_events_by_metadata() {
+        let registry = TypeRegistry::default();
+        let current = current_metadata();
+        // A runtime whose modules are in another order, `XAssets::Change` has other arguments
+        // and `XAssets::Unknown` is removed from the current runtime.
+        let metadata = Metadata::with_events(&[
+            (
+                "System",
+                &[("ExtrinsicSuccess", &[]), ("ExtrinsicFailed", &[])],
+            ),
+            (
+                "XAssets",
+                &[
+                    ("NewAccount", &["T::AccountId"]),
+                    ("Change", &["Token", "AccountId", "AssetType", "Balance"]),
+                    ("Unknown", &["u32"]),
+                ],
+            ),
+            (
+                "Indices",
+                &[("NewAccountIndex", &["AccountId", "AccountIndex"])],
+            ),
+        ]);
+
+        let mut data = Compact(5u32).encode();
+        // [ApplyExtrinsic(1), Indices::NewAccountIndex(0x11..., 7), no topics]
+        (0u8, 1u32, 2u8, 0u8, [0x11u8; 32], 7u32, 0u8).encode_to(&mut data);
+        // [ApplyExtrinsic(1), XAssets::Unknown(5), no topics]
+        (0u8, 1u32, 1u8, 2u8, 5u32, 0u8).encode_to(&mut data);
+        // [ApplyExtrinsic(1), XAssets::Change(BTC, 0x12..., Free, 5), no topics]
+        (0u8, 1u32, 1u8, 1u8, b"BTC".to_vec(), [0x12u8; 32]).encode_to(&mut data);
+        (0u8, 5u64, 0u8).encode_to(&mut data);
+        // [ApplyExtrinsic(1), XAssets::NewAccount(0x13...), no topics]
+        (0u8, 1u32, 1u8, 0u8, [0x13u8; 32], 0u8).encode_to(&mut data);
+        // [ApplyExtrinsic(1), System::ExtrinsicSuccess, no topics]
+        (0u8, 1u32, 0u8, 0u8, 0u8).encode_to(&mut data);
+
+        let (decoder, decoded) =
+            decode_events_by_metadata(&registry, &metadata, &current, EventsDecoder::ALL, &data)
+                .unwrap();
+        assert_eq!(decoder, EventsDecoder::Current);
+        assert_eq!(decoded.skipped, vec![1, 2]);
+        assert_eq!(
+            new_account_indices(&decoded.records),
+            vec![(AccountId::repeat_byte(0x11), 7)]
+        );
+        assert_eq!(
+            new_accounts(&decoded.records),
+            vec![AccountId::repeat_byte(0x13)]
+        );
+        assert_eq!(extrinsic_results(&decoded.records).get(&1), Some(&true));
+        assert_eq!(
+            created_accounts(&decoded.records),
+            vec![AccountId::repeat_byte(0x11), AccountId::repeat_byte(0x13)]
+        );
+
+        // [ApplyExtrinsic(2), XAssets::NewAccount(0x14...)] of a runtime without topics
+        let mut data = Compact(1u32).encode();
+        (0u8, 2u32, 1u8, 0u8, [0x14u8; 32]).encode_to(&mut data);
+        let (decoder, decoded) =
+            decode_events_by_metadata(&registry, &metadata, &current, EventsDecoder::ALL, &data)
+                .unwrap();
+        assert_eq!(decoder, EventsDecoder::WithoutTopics);
+        assert_eq!(
+            decoded,
+            DecodedEvents::from(vec![EventRecord {
+                phase: Phase::ApplyExtrinsic(2),
+                event: new_account_event(0x14),
+                topics: vec![],
+            }])
+        );
+
+        // The event is unknown to the metadata of its runtime.
+        let mut data = Compact(1u32).encode();
+        (0u8, 2u32, 1u8, 3u8, 0u8).encode_to(&mut data);
+        assert!(decode_events_by_metadata(
+            &registry,
+            &metadata,
+            &current,
+            EventsDecoder::ALL,
+            &data
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_decoders_of_spec_version() {
+        assert_eq!(EventsDecoder::of_spec_version(0), &[EventsDecoder::Current]);
+        assert_eq!(
+            EventsDecoder::with_alternatives(u32::MAX),
+            EventsDecoder::ALL
+        );
+    }
+}
@@ -0,0 +1,138 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+use anyhow::{anyhow, Result};
+use codec::{Compact, Decode};
+use sp_core::ed25519;
+use sp_runtime::generic::Era;
+
+use crate::chainx::{decode::Call, types::*};
+
+/// The version of the extrinsic format, the highest bit is set for the signed extrinsics.
+const EXTRINSIC_VERSION: u8 = 1;
+
+/// The signature part of a ChainX 1.0 extrinsic.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ExtrinsicSignature {
+    pub signer: Address,
+    pub signature: ed25519::Signature,
+    pub nonce: Index,
+    pub era: Era,
+    /// The multiplier of the transaction fee, which speeds up the extrinsic.
+    pub acceleration: Acceleration,
+}
+
+/// A ChainX 1.0 extrinsic, whose call is kept encoded so that it can be decoded by either the
+/// hand-written `Call` or the runtime metadata.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct UncheckedExtrinsic {
+    pub signature: Option<ExtrinsicSignature>,
+    pub call: Vec<u8>,
+}
+
+impl UncheckedExtrinsic {
+    /// Decode an extrinsic of `chain_getBlock`, which is prefixed with its compact length.
+    pub fn decode_from(data: &[u8]) -> Result<Self> {
+        let mut input = data;
+        let len = <Compact<u32>>::decode(&mut input)?.0 as usize;
+        if len != input.len() {
+            return Err(anyhow!(
+                "Extrinsic length prefix {} mismatches the remaining {} bytes",
+                len,
+                input.len()
+            ));
+        }
+
+        let version = u8::decode(&mut input)?;
+        if version & 0x7f != EXTRINSIC_VERSION {
+            return Err(anyhow!("Unsupported extrinsic version: {:#x}", version));
+        }
+        let signature = if version & 0x80 != 0 {
+            Some(ExtrinsicSignature {
+                signer: Decode::decode(&mut input)?,
+                signature: Decode::decode(&mut input)?,
+                nonce: <Compact<Index>>::decode(&mut input)?.0,
+                era: Decode::decode(&mut input)?,
+                acceleration: <Compact<Acceleration>>::decode(&mut input)?.0,
+            })
+        } else {
+            None
+        };
+        Ok(Self {
+            signature,
+            call: input.to_vec(),
+        })
+    }
+
+    /// Decode the call with the hand-written `Call`.
+    pub fn decode_call(&self) -> Result<Call> {
+        Ok(Call::decode(&mut self.call.as_slice())?)
+    }
+}
+
+/// An extrinsic of a block, with the result of its execution.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct BlockExtrinsic {
+    pub index: u32,
+    pub extrinsic: UncheckedExtrinsic,
+    /// Whether the extrinsic succeeded, `None` if it's unknown since the events of the block
+    /// were not fully decoded.
+    pub success: Option<bool>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chainx::decode::XAssetsCall;
+    use codec::Encode;
+
+    #[test]
+    fn test_decode_extrinsic() -> Result<()> {
+        // XAssets::transfer(Address::Id(0x22...), "PCX", 100, "memo")
+        let mut call = vec![8u8, 3u8];
+        (
+            0xffu8,
+            [0x22u8; 32],
+            b"PCX".to_vec(),
+            100u64,
+            b"memo".to_vec(),
+        )
+            .encode_to(&mut call);
+
+        let mut data = vec![0x81u8, 0xff];
+        data.extend_from_slice(&[0x11; 32]);
+        data.extend_from_slice(&[0xaa; 64]);
+        // nonce 5, immortal, acceleration 1
+        (Compact(5u64), 0u8, Compact(1u32)).encode_to(&mut data);
+        data.extend_from_slice(&call);
+
+        let extrinsic = UncheckedExtrinsic::decode_from(&data.encode())?;
+        let signature = extrinsic.signature.clone().unwrap();
+        assert_eq!(signature.signer, Address::Id(AccountId::repeat_byte(0x11)));
+        assert_eq!(signature.nonce, 5);
+        assert_eq!(signature.era, Era::Immortal);
+        assert_eq!(signature.acceleration, 1);
+        assert_eq!(extrinsic.call, call);
+        assert_eq!(
+            extrinsic.decode_call()?,
+            Call::XAssets(XAssetsCall::transfer(
+                Address::Id(AccountId::repeat_byte(0x22)),
+                b"PCX".to_vec(),
+                100,
+                b"memo".to_vec()
+            ))
+        );
+
+        // Timestamp::set is unsigned
+        let unsigned = vec![0x01u8, 1, 0, 0x0b, 0xa0];
+        let extrinsic = UncheckedExtrinsic::decode_from(&unsigned.encode())?;
+        assert_eq!(extrinsic.signature, None);
+        assert_eq!(extrinsic.call, vec![1, 0, 0x0b, 0xa0]);
+
+        // The length prefix is required and must cover exactly the rest.
+        assert!(UncheckedExtrinsic::decode_from(&unsigned).is_err());
+        let mut truncated = unsigned.encode();
+        truncated.pop();
+        assert!(UncheckedExtrinsic::decode_from(&truncated).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,297 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+use anyhow::{anyhow, Result};
+use codec::Decode;
+
+/// The magic number at the beginning of the runtime metadata, `meta` in little endian.
+const META_RESERVED: u32 = 0x6174_656d;
+
+/// The calls and events of a runtime, parsed from the `state_getMetadata`.
+///
+/// Only the metadata V1 - V8 are supported, the storage entries, constants and errors are
+/// skipped since only the calls and events are needed for decoding.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Metadata {
+    pub version: u8,
+    pub modules: Vec<ModuleMetadata>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ModuleMetadata {
+    pub name: String,
+    pub calls: Option<Vec<FunctionMetadata>>,
+    pub events: Option<Vec<EventMetadata>>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FunctionMetadata {
+    pub name: String,
+    /// The names and types of the arguments.
+    pub arguments: Vec<(String, String)>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EventMetadata {
+    pub name: String,
+    /// The types of the arguments.
+    pub arguments: Vec<String>,
+}
+
+impl Metadata {
+    pub fn parse(data: &[u8]) -> Result<Self> {
+        let input = &mut &data[..];
+        let magic: u32 = decode(input)?;
+        if magic != META_RESERVED {
+            return Err(anyhow!("Invalid metadata magic number: {:#x}", magic));
+        }
+        let version: u8 = decode(input)?;
+        if !(1..=8).contains(&version) {
+            return Err(anyhow!("Unsupported metadata version: V{}", version));
+        }
+
+        let module_number = decode::<codec::Compact<u32>>(input)?.0;
+        let mut modules = Vec::with_capacity(module_number as usize);
+        for _ in 0..module_number {
+            modules.push(parse_module(version, input)?);
+        }
+        Ok(Self { version, modules })
+    }
+
+    /// The module and the call of the call index, the index of a module only counts the modules
+    /// with calls.
+    pub fn call(&self, module: u8, call: u8) -> Option<(&ModuleMetadata, &FunctionMetadata)> {
+        let module = self
+            .modules
+            .iter()
+            .filter(|module| module.calls.is_some())
+            .nth(module as usize)?;
+        let call = module.calls.as_ref()?.get(call as usize)?;
+        Some((module, call))
+    }
+
+    /// The module and the event of the event index, the index of a module only counts the
+    /// modules with events.
+    pub fn event(&self, module: u8, event: u8) -> Option<(&ModuleMetadata, &EventMetadata)> {
+        let module = self
+            .modules
+            .iter()
+            .filter(|module| module.events.is_some())
+            .nth(module as usize)?;
+        let event = module.events.as_ref()?.get(event as usize)?;
+        Some((module, event))
+    }
+
+    /// The event index of the event of the module, the reverse of `event`.
+    pub fn event_index(&self, module: &str, event: &str) -> Option<(u8, u8)> {
+        let (module_index, module) = self
+            .modules
+            .iter()
+            .filter(|m| m.events.is_some())
+            .enumerate()
+            .find(|(_, m)| m.name == module)?;
+        let event_index = module
+            .events
+            .as_ref()?
+            .iter()
+            .position(|e| e.name == event)?;
+        Some((module_index as u8, event_index as u8))
+    }
+}
+
+#[cfg(test)]
+impl Metadata {
+    /// The metadata of the modules with the events: `(module, [(event, [argument type])])`.
+    pub(crate) fn with_events(modules: &[(&str, &[(&str, &[&str])])]) -> Self {
+        let modules = modules
+            .iter()
+            .map(|(name, events)| ModuleMetadata {
+                name: name.to_string(),
+                calls: None,
+                events: Some(
+                    events
+                        .iter()
+                        .map(|(name, arguments)| EventMetadata {
+                            name: name.to_string(),
+                            arguments: arguments.iter().map(|ty| ty.to_string()).collect(),
+                        })
+                        .collect(),
+                ),
+            })
+            .collect();
+        Self {
+            version: 8,
+            modules,
+        }
+    }
+}
+
+fn decode<T: Decode>(input: &mut &[u8]) -> Result<T> {
+    T::decode(input).map_err(|err| anyhow!("Invalid metadata: {}", err))
+}
+
+fn parse_module(version: u8, input: &mut &[u8]) -> Result<ModuleMetadata> {
+    let name: String = decode(input)?;
+    if version <= 6 {
+        let _prefix: String = decode(input)?;
+    }
+    if decode::<bool>(input)? {
+        if version >= 7 {
+            let _prefix: String = decode(input)?;
+        }
+        let entry_number = decode::<codec::Compact<u32>>(input)?.0;
+        for _ in 0..entry_number {
+            skip_storage_entry(version, input)?;
+        }
+    }
+    let calls = if decode::<bool>(input)? {
+        let call_number = decode::<codec::Compact<u32>>(input)?.0;
+        let mut calls = Vec::with_capacity(call_number as usize);
+        for _ in 0..call_number {
+            let name = decode(input)?;
+            let arguments = decode(input)?;
+            let _documentation: Vec<String> = decode(input)?;
+            calls.push(FunctionMetadata { name, arguments });
+        }
+        Some(calls)
+    } else {
+        None
+    };
+    let events = if decode::<bool>(input)? {
+        let event_number = decode::<codec::Compact<u32>>(input)?.0;
+        let mut events = Vec::with_capacity(event_number as usize);
+        for _ in 0..event_number {
+            let name = decode(input)?;
+            let arguments = decode(input)?;
+            let _documentation: Vec<String> = decode(input)?;
+            events.push(EventMetadata { name, arguments });
+        }
+        Some(events)
+    } else {
+        None
+    };
+    if version >= 6 {
+        // (name, type, value, documentation)
+        let _constants: Vec<(String, String, Vec<u8>, Vec<String>)> = decode(input)?;
+    }
+    if version >= 8 {
+        // (name, documentation)
+        let _errors: Vec<(String, Vec<String>)> = decode(input)?;
+    }
+    Ok(ModuleMetadata {
+        name,
+        calls,
+        events,
+    })
+}
+
+fn skip_storage_entry(version: u8, input: &mut &[u8]) -> Result<()> {
+    let _name: String = decode(input)?;
+    let _modifier: u8 = decode(input)?;
+    match decode::<u8>(input)? {
+        // Plain(value)
+        0 => {
+            let _value: String = decode(input)?;
+        }
+        // Map { hasher, key, value, is_linked }
+        1 => {
+            if version >= 4 {
+                let _hasher: u8 = decode(input)?;
+            }
+            let _key_value: (String, String) = decode(input)?;
+            if version >= 2 {
+                let _is_linked: bool = decode(input)?;
+            }
+        }
+        // DoubleMap { hasher, key1, key2, value, key2_hasher }
+        2 if version >= 3 => {
+            if version >= 4 {
+                let _hasher: u8 = decode(input)?;
+            }
+            let _keys_value: (String, String, String) = decode(input)?;
+            if version >= 5 {
+                let _key2_hasher: u8 = decode(input)?;
+            } else {
+                let _key2_hasher: String = decode(input)?;
+            }
+        }
+        ty => return Err(anyhow!("Invalid metadata: unknown storage type {}", ty)),
+    }
+    let _default: Vec<u8> = decode(input)?;
+    let _documentation: Vec<String> = decode(input)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codec::Encode;
+
+    fn s(value: &str) -> String {
+        value.to_string()
+    }
+
+    #[test]
+    fn test_parse_metadata_v3() -> Result<()> {
+        let mut data = META_RESERVED.encode();
+        data.push(3);
+        codec::Compact(2u32).encode_to(&mut data);
+
+        // System: storage only
+        s("System").encode_to(&mut data);
+        s("System").encode_to(&mut data);
+        true.encode_to(&mut data);
+        codec::Compact(2u32).encode_to(&mut data);
+        // AccountNonce: Map { key, value, is_linked }
+        (
+            s("AccountNonce"),
+            1u8,
+            1u8,
+            s("T::AccountId"),
+            s("T::Index"),
+            false,
+        )
+            .encode_to(&mut data);
+        (Vec::<u8>::new(), Vec::<String>::new()).encode_to(&mut data);
+        // Events: Plain(value)
+        (s("Events"), 1u8, 0u8, s("Vec<EventRecord<T::Event>>")).encode_to(&mut data);
+        (Vec::<u8>::new(), vec![s("The events")]).encode_to(&mut data);
+        (false, false).encode_to(&mut data);
+
+        // XAssets: calls and events
+        s("XAssets").encode_to(&mut data);
+        s("XAssets").encode_to(&mut data);
+        false.encode_to(&mut data);
+        true.encode_to(&mut data);
+        vec![(
+            s("transfer"),
+            vec![
+                (s("dest"), s("<T::Lookup as StaticLookup>::Source")),
+                (s("token"), s("Token")),
+            ],
+            vec![s("Transfer the token")],
+        )]
+        .encode_to(&mut data);
+        true.encode_to(&mut data);
+        vec![(s("NewAccount"), vec![s("AccountId")], Vec::<String>::new())].encode_to(&mut data);
+
+        let metadata = Metadata::parse(&data)?;
+        assert_eq!(metadata.version, 3);
+        assert_eq!(metadata.modules.len(), 2);
+        assert_eq!(metadata.modules[0].calls, None);
+
+        let (module, call) = metadata.call(0, 0).unwrap();
+        assert_eq!(module.name, "XAssets");
+        assert_eq!(call.name, "transfer");
+        assert_eq!(call.arguments[1], (s("token"), s("Token")));
+        let (module, event) = metadata.event(0, 0).unwrap();
+        assert_eq!(module.name, "XAssets");
+        assert_eq!(event.arguments, vec![s("AccountId")]);
+        assert!(metadata.event(1, 0).is_none());
+        assert_eq!(metadata.event_index("XAssets", "NewAccount"), Some((0, 0)));
+        assert_eq!(metadata.event_index("System", "NewAccount"), None);
+
+        data[4] = 0;
+        assert!(Metadata::parse(&data).is_err());
+        Ok(())
+    }
+}
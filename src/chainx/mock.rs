@@ -0,0 +1,259 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+use std::{collections::HashMap, fs::File, path::Path};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// A rpc request and its response.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RpcRecord {
+    pub method: String,
+    pub params: Vec<Value>,
+    pub result: Value,
+}
+
+/// An in-process ChainX node serving the recorded rpc responses, which is used to run the
+/// exporter without network.
+///
+/// The `state_getStorage` requests that are not recorded are regarded as empty storages,
+/// other requests that are not recorded fail like the unknown rpc methods.
+#[derive(Clone, Debug, Default)]
+pub struct MockNode {
+    responses: HashMap<String, Value>,
+}
+
+impl MockNode {
+    /// Load the recorded responses from a fixture file, which is a JSON array of `RpcRecord`.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let file = File::open(path)?;
+        let records: Vec<RpcRecord> = serde_json::from_reader(file)?;
+        let mut node = Self::default();
+        node.extend(records);
+        Ok(node)
+    }
+
+    /// Record the response of a request.
+    pub fn insert<S: Into<String>>(&mut self, method: S, params: Vec<Value>, result: Value) {
+        let method = method.into();
+        self.responses.insert(request_key(&method, &params), result);
+    }
+
+    pub fn extend<I: IntoIterator<Item = RpcRecord>>(&mut self, records: I) {
+        for record in records {
+            self.insert(record.method, record.params, record.result);
+        }
+    }
+
+    /// Serve a request with the recorded response.
+    pub fn request(&self, method: &str, params: &[Value]) -> Result<Value> {
+        match self.responses.get(&request_key(method, params)) {
+            Some(result) => Ok(result.clone()),
+            None if method == "state_getStorage" => Ok(Value::Null),
+            None => Err(anyhow!(
+                "Mock node: no response of `{}` with params {}",
+                method,
+                Value::Array(params.to_vec())
+            )),
+        }
+    }
+}
+
+/// The params are serialized as the compact JSON, which is stable for the same request.
+pub(crate) fn request_key(method: &str, params: &[Value]) -> String {
+    format!("{}{}", method, Value::Array(params.to_vec()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chainx::*;
+
+    use codec::Encode;
+    use serde_json::{json, to_value as to_json_value};
+    use sp_core::{
+        blake2_256,
+        storage::{StorageData, StorageKey},
+        twox_128,
+    };
+
+    const FIXTURE: &str = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/new_account.json"
+    );
+
+    fn account(byte: u8) -> AccountId {
+        AccountId::repeat_byte(byte)
+    }
+
+    fn storage_params(key: Vec<u8>, hash: Hash) -> Vec<Value> {
+        vec![
+            to_json_value(StorageKey(key)).unwrap(),
+            to_json_value(Some(hash)).unwrap(),
+        ]
+    }
+
+    fn storage_result<T: Encode>(value: T) -> Value {
+        to_json_value(StorageData(value.encode())).unwrap()
+    }
+
+    #[async_std::test]
+    async fn test_new_account() -> Result<()> {
+        let chainx = ChainX::mock(MockNode::load(FIXTURE)?);
+
+        let accounts = chainx.new_account(0, vec![1, 2, 3]).await?;
+        assert_eq!(
+            accounts,
+            vec![(1, account(0x11)), (1, account(0x12)), (3, account(0x13))]
+        );
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_spec_version() -> Result<()> {
+        // The runtime is upgraded from spec version 1 to 2 at block #6.
+        let mut node = MockNode::default();
+        for height in 1..=8u64 {
+            let hash = Hash::repeat_byte(height as u8);
+            node.insert(
+                "chain_getBlockHash",
+                vec![json!(height)],
+                to_json_value(hash)?,
+            );
+            node.insert(
+                "state_getRuntimeVersion",
+                vec![to_json_value(Some(hash))?],
+                json!({
+                    "specName": "chainx",
+                    "implName": "chainx-net",
+                    "authoringVersion": 1,
+                    "specVersion": if height < 6 { 1 } else { 2 },
+                    "implVersion": 0,
+                    "apis": []
+                }),
+            );
+        }
+
+        let chainx = ChainX::mock(node);
+        for &height in &[8, 1, 4, 2, 7, 3, 6, 5] {
+            let expected = if height < 6 { 1 } else { 2 };
+            assert_eq!(chainx.spec_version(height).await?, expected);
+        }
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_raw_psedu_intentions() -> Result<()> {
+        let hash = Hash::repeat_byte(0xaa);
+        let token = b"BTC".to_vec();
+
+        let mut node = MockNode::default();
+        node.insert(
+            "state_getStorage",
+            storage_params(twox_128(b"XTokens PseduIntentions").to_vec(), hash),
+            storage_result(vec![token.clone()]),
+        );
+        let mut key = b"XAssets AssetInfo".to_vec();
+        token.encode_to(&mut key);
+        // (Asset { token, token_name, chain, precision, desc }, is_online, init_number)
+        let asset_info = (
+            (
+                token.clone(),
+                b"X-BTC".to_vec(),
+                Chain::Bitcoin,
+                8u16,
+                Vec::<u8>::new(),
+            ),
+            true,
+            100u64,
+        );
+        node.insert(
+            "state_getStorage",
+            storage_params(blake2_256(&key).to_vec(), hash),
+            storage_result(asset_info),
+        );
+        let mut key = b"XTokens PseduIntentionProfiles".to_vec();
+        token.encode_to(&mut key);
+        // (last_total_deposit_weight, last_total_deposit_weight_update)
+        node.insert(
+            "state_getStorage",
+            storage_params(blake2_256(&key).to_vec(), hash),
+            storage_result((5_000u64, 200u64)),
+        );
+        let mut key = b"XTokens TokenDiscount".to_vec();
+        token.encode_to(&mut key);
+        node.insert(
+            "state_getStorage",
+            storage_params(blake2_256(&key).to_vec(), hash),
+            storage_result(50u32),
+        );
+        let mut key = b"XAssets TotalAssetBalance".to_vec();
+        token.encode_to(&mut key);
+        let total: CompatibleBTreeMap<AssetType, Balance> =
+            vec![(AssetType::Free, 300), (AssetType::ReservedWithdrawal, 20)]
+                .into_iter()
+                .collect();
+        node.insert(
+            "state_getStorage",
+            storage_params(blake2_256(&key).to_vec(), hash),
+            storage_result(total),
+        );
+
+        let chainx = ChainX::mock(node);
+        let intentions = chainx.raw_psedu_intentions(Some(hash)).await?.unwrap();
+        assert_eq!(intentions.len(), 1);
+        let btc = &intentions[0];
+        assert_eq!(btc.id, "BTC");
+        assert_eq!(btc.circulation, 320);
+        assert_eq!(btc.discount, 50);
+        assert_eq!(btc.jackpot, 0);
+        assert_eq!(btc.last_total_deposit_weight, 5_000);
+        assert_eq!(btc.last_total_deposit_weight_update, 200);
+        Ok(())
+    }
+
+    #[async_std::test]
+    async fn test_account_vote_weight_v1() -> Result<()> {
+        let hash = Hash::repeat_byte(0xbb);
+        let who = account(0x01);
+        let node = account(0x02);
+
+        // `chainx_getNominationRecords` is not recorded, so the v1 rpc will be used.
+        let mut mock = MockNode::default();
+        mock.insert(
+            "chainx_getNominationRecordsV1",
+            vec![to_json_value(who)?, to_json_value(Some(hash))?],
+            json!([[
+                node,
+                {
+                    "nomination": 10,
+                    "lastVoteWeight": "340282366920938463463374607431768211000",
+                    "lastVoteWeightUpdate": 90,
+                    "revocations": [{ "blockNumber": 120, "value": 5 }]
+                }
+            ]]),
+        );
+
+        let chainx = ChainX::mock(mock);
+        let vote_weight = chainx
+            .account_vote_weight_v1(&who, Some(hash), 100)
+            .await?
+            .unwrap();
+        assert_eq!(
+            vote_weight,
+            vec![AccountVoteWeightInfoV1 {
+                node_vote_weight: NodeVoteWeightInfoV1 {
+                    account: node,
+                    nomination: 10,
+                    weight: 340_282_366_920_938_463_463_374_607_431_768_211_100,
+                },
+                revocations: vec![Revocation {
+                    block_number: 120,
+                    value: 5
+                }],
+            }]
+        );
+        Ok(())
+    }
+}
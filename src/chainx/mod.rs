@@ -1,75 +1,457 @@
 // Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
 
+mod cassette;
 mod decode;
+mod dynamic;
+mod events;
+mod extrinsic;
+mod metadata;
+mod mock;
+mod retry;
 mod rpc;
 mod types;
 
+pub use self::cassette::{Cassette, CassetteConfig, CassetteMode};
 pub use self::decode::*;
+pub use self::dynamic::{TypeDef, TypeRegistry};
+#[cfg(test)]
+pub(crate) use self::events::current_metadata;
+pub use self::events::{
+    created_accounts, decode_dynamic_events, decode_events, decode_events_by_metadata,
+    extrinsic_results, new_account_indices, new_accounts, ChainXEventRecord, DecodeEventsError,
+    DecodedEvents, EventsDecoder,
+};
+pub use self::extrinsic::{BlockExtrinsic, ExtrinsicSignature, UncheckedExtrinsic};
+pub use self::metadata::{EventMetadata, FunctionMetadata, Metadata, ModuleMetadata};
+pub use self::mock::{MockNode, RpcRecord};
+pub use self::retry::{RetryConfig, RpcError};
 pub use self::types::*;
 
-use crate::log_missing_block_height;
-use anyhow::Result;
-use codec::Decode;
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+};
+
+use self::retry::WsClient;
+use crate::{log_missing_block_height, log_skipped_events, Config};
+use anyhow::{anyhow, Result};
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::Value;
 use sp_core::{
     storage::{StorageData, StorageKey},
     twox_128,
 };
 use url::Url;
-use web3::transports::WebSocket;
 
 /// The ChainX Rpc client
 #[derive(Clone)]
 pub struct ChainX {
-    client: WebSocket,
+    transport: Transport,
+    /// The known spec versions of the runtime at some block heights.
+    spec_versions: Arc<Mutex<BTreeMap<BlockNumber, u32>>>,
+    /// The metadata of the runtimes, keyed by the spec version.
+    metadata: Arc<Mutex<BTreeMap<u32, Arc<Metadata>>>>,
+    /// The spec version of the latest runtime.
+    current_spec_version: Arc<Mutex<Option<u32>>>,
+    registry: Arc<TypeRegistry>,
+}
+
+#[derive(Clone)]
+enum Transport {
+    WebSocket(WsClient),
+    Mock(Arc<MockNode>),
+    Record(WsClient, Arc<Cassette>),
+    Replay(Arc<Cassette>),
 }
 
 impl ChainX {
-    /// Creates a new Rpc Client.
+    /// Creates a new Rpc Client with the default retry policy.
     pub async fn new(url: &Url) -> Result<Self> {
-        assert!(
-            url.as_str().starts_with("ws://") || url.as_str().starts_with("wss://"),
-            "the url accepts websocket protocol only"
-        );
-        let client = WebSocket::new(url.as_str()).await?;
-        Ok(Self { client })
+        Self::with_retry(url, RetryConfig::default()).await
+    }
+
+    /// Creates a new Rpc Client with the given retry policy.
+    pub async fn with_retry(url: &Url, retry: RetryConfig) -> Result<Self> {
+        let client = connect_websocket(url, retry).await?;
+        Ok(Self::from_transport(Transport::WebSocket(client)))
+    }
+
+    /// Creates a new Rpc Client which records every response into the cassette.
+    pub async fn record(url: &Url, retry: RetryConfig, cassette: Cassette) -> Result<Self> {
+        let client = connect_websocket(url, retry).await?;
+        Ok(Self::from_transport(Transport::Record(
+            client,
+            Arc::new(cassette),
+        )))
+    }
+
+    /// Creates a Rpc Client serving the requests from the cassette, without connecting to a node.
+    pub fn replay(cassette: Cassette) -> Self {
+        Self::from_transport(Transport::Replay(Arc::new(cassette)))
+    }
+
+    /// Creates a Rpc Client connected to an in-process mock node.
+    pub fn mock(node: MockNode) -> Self {
+        Self::from_transport(Transport::Mock(Arc::new(node)))
+    }
+
+    fn from_transport(transport: Transport) -> Self {
+        Self {
+            transport,
+            spec_versions: Default::default(),
+            metadata: Default::default(),
+            current_spec_version: Default::default(),
+            registry: Default::default(),
+        }
+    }
+
+    /// Decode the values with the given types besides the ChainX 1.0 types.
+    pub fn with_types(mut self, types: &BTreeMap<String, TypeDef>) -> Self {
+        self.registry = Arc::new(TypeRegistry::with_types(types));
+        self
+    }
+
+    /// The types of the metadata driven decoder.
+    pub fn registry(&self) -> &TypeRegistry {
+        &self.registry
+    }
+
+    /// Creates a new Rpc Client from the config.
+    ///
+    /// A `file://` url is regarded as the fixture file of the mock node.
+    pub async fn connect(conf: &Config) -> Result<Self> {
+        let url = &conf.chainx_ws_url;
+        let chainx = match &conf.cassette {
+            Some(cassette) if cassette.mode == CassetteMode::Replay => {
+                Self::replay(Cassette::open(&cassette.dir)?)
+            }
+            Some(cassette) => Self::record(url, conf.retry, Cassette::open(&cassette.dir)?).await?,
+            None if url.scheme() == "file" => {
+                let path = url
+                    .to_file_path()
+                    .map_err(|_| anyhow!("Invalid fixture path: {}", url))?;
+                Self::mock(MockNode::load(path)?)
+            }
+            None => Self::with_retry(url, conf.retry).await?,
+        };
+        Ok(chainx.with_types(&conf.types))
+    }
+
+    /// Execute a rpc request through the underlying transport.
+    pub(crate) async fn request(&self, method: &str, params: Vec<Value>) -> Result<Value> {
+        match &self.transport {
+            Transport::WebSocket(client) => client.execute(method, params).await,
+            Transport::Mock(node) => node.request(method, &params),
+            Transport::Record(client, cassette) => {
+                let result = client.execute(method, params.clone()).await?;
+                cassette.record(method, &params, &result)?;
+                Ok(result)
+            }
+            Transport::Replay(cassette) => cassette.replay(method, &params),
+        }
+    }
+
+    /// Execute a batch of requests of the same rpc method through the underlying transport.
+    pub(crate) async fn request_batch(
+        &self,
+        method: &str,
+        batch: Vec<Vec<Value>>,
+    ) -> Result<Vec<Result<Value>>> {
+        match &self.transport {
+            Transport::WebSocket(client) => client.execute_batch(method, batch).await,
+            Transport::Mock(node) => Ok(batch
+                .iter()
+                .map(|params| node.request(method, params))
+                .collect()),
+            Transport::Record(client, cassette) => {
+                let responses = client.execute_batch(method, batch.clone()).await?;
+                for (params, response) in batch.iter().zip(&responses) {
+                    if let Ok(result) = response {
+                        cassette.record(method, params, result)?;
+                    }
+                }
+                Ok(responses)
+            }
+            Transport::Replay(cassette) => Ok(batch
+                .iter()
+                .map(|params| cassette.replay(method, params))
+                .collect()),
+        }
     }
 
     pub async fn new_account(&self, id: usize, heights: Vec<u64>) -> Result<Vec<(u64, AccountId)>> {
         assert!(!heights.is_empty());
         let mut accounts = vec![];
         let (begin, end) = (*heights.first().unwrap(), *heights.last().unwrap());
+        // So that the blocks of the same runtime as the last block won't fetch the version again.
+        self.spec_version(end).await?;
         for height in heights {
-            let storage = self.system_events(height).await?;
             log::info!("[{}] [{}-{}] Block Height {}", id, begin, end, height);
-            if let Some(storage) = storage {
-                let event_records: Vec<EventRecord<ChainXEvent, Hash>> =
-                    match Decode::decode(&mut storage.0.as_slice()) {
-                        Ok(records) => records,
-                        Err(err) => {
-                            log::error!("Block Height {}, err: {}", height, err);
-                            log_missing_block_height(height)?;
-                            continue;
-                        }
-                    };
-                for event_record in event_records {
-                    if let ChainXEvent::XAssets(XAssetsEvent::NewAccount(account)) =
-                        event_record.event
-                    {
+            let storage = match self.system_events(height).await? {
+                Some(storage) => storage,
+                None => continue,
+            };
+            match self.decode_block_events(height, &storage.0).await? {
+                Ok(events) => {
+                    for account in created_accounts(&events.records) {
                         accounts.push((height, account))
                     }
+                    // The accounts are still recovered from the other events, the skipped ones
+                    // are reported for checking by hand.
+                    if !events.skipped.is_empty() {
+                        log::warn!(
+                            "Block Height {}, skipped events {:?}",
+                            height,
+                            events.skipped
+                        );
+                        log_skipped_events(height, &events.skipped)?;
+                    }
+                }
+                // The block is retried by `retry-missing` later.
+                Err(err) => {
+                    log::error!("Block Height {}, err: {}", height, err);
+                    log_missing_block_height(height)?;
                 }
             }
         }
         Ok(accounts)
     }
 
+    /// The event records of the block, decoded by the metadata of its runtime.
+    ///
+    /// Fails if the events can't be decoded, or some of them are unknown to the current runtime.
+    pub async fn event_records(&self, height: BlockNumber) -> Result<Vec<ChainXEventRecord>> {
+        self.decode_event_records(height)
+            .await?
+            .map_err(|err| anyhow!("Block Height {}, err: {}", height, err))
+    }
+
+    /// The outer error is of the RPC requests, the inner one is of decoding the events.
+    async fn decode_event_records(
+        &self,
+        height: BlockNumber,
+    ) -> Result<Result<Vec<ChainXEventRecord>, String>> {
+        let storage = match self.system_events(height).await? {
+            Some(storage) => storage,
+            None => return Ok(Ok(vec![])),
+        };
+        let events = match self.decode_block_events(height, &storage.0).await? {
+            Ok(events) => events,
+            Err(err) => return Ok(Err(err)),
+        };
+        if events.skipped.is_empty() {
+            Ok(Ok(events.records))
+        } else {
+            Ok(Err(format!(
+                "Events {:?} are unknown to the current runtime",
+                events.skipped
+            )))
+        }
+    }
+
+    /// Decode the `System Events` storage of the block into the `ChainXEvent`s by the metadata
+    /// of its runtime, with the record layout of its spec version and the alternatives.
+    ///
+    /// The outer error is of the RPC requests, the inner one is of decoding the events.
+    pub async fn decode_block_events(
+        &self,
+        height: BlockNumber,
+        data: &[u8],
+    ) -> Result<Result<DecodedEvents, String>> {
+        let spec_version = self.spec_version(height).await?;
+        let metadata = self.runtime_metadata(height).await?;
+        let current = self.current_metadata().await?;
+        let decoders = EventsDecoder::with_alternatives(spec_version);
+        Ok(
+            decode_events_by_metadata(&self.registry, &metadata, &current, &decoders, data)
+                .map(|(_, events)| events)
+                .map_err(|err| err.to_string()),
+        )
+    }
+
     pub async fn system_events(&self, height: u64) -> Result<Option<StorageData>> {
         let hash = self.block_hash(Some(height)).await?;
         let key = StorageKey(twox_128(b"System Events").to_vec());
         self.storage(&key, hash).await
     }
 
+    /// The spec version of the runtime at the block.
+    ///
+    /// The spec version never decreases, so a block between two known blocks of the same version
+    /// has that version too, otherwise the runtime upgrade is located by bisection.
+    pub async fn spec_version(&self, height: BlockNumber) -> Result<u32> {
+        loop {
+            let (floor, ceil) = {
+                let versions = self
+                    .spec_versions
+                    .lock()
+                    .expect("spec versions lock poisoned; qed");
+                if let Some(version) = versions.get(&height) {
+                    return Ok(*version);
+                }
+                (
+                    versions.range(..height).next_back().map(|(h, v)| (*h, *v)),
+                    versions.range(height..).next().map(|(h, v)| (*h, *v)),
+                )
+            };
+            let probe = match (floor, ceil) {
+                (Some((_, low)), Some((_, high))) if low == high => return Ok(low),
+                (Some((floor, _)), Some((ceil, _))) => floor + (ceil - floor) / 2,
+                _ => height,
+            };
+
+            let hash = self
+                .block_hash(Some(probe))
+                .await?
+                .ok_or_else(|| anyhow!("Block #{} not found", probe))?;
+            let version = self.runtime_version(Some(hash)).await?;
+            log::debug!(
+                "Block Height {}, Spec Version {}",
+                probe,
+                version.spec_version
+            );
+            self.spec_versions
+                .lock()
+                .expect("spec versions lock poisoned; qed")
+                .insert(probe, version.spec_version);
+        }
+    }
+
+    /// The metadata of the runtime at the block, which is shared by the blocks of the same
+    /// spec version.
+    pub async fn runtime_metadata(&self, height: BlockNumber) -> Result<Arc<Metadata>> {
+        let spec_version = self.spec_version(height).await?;
+        self.spec_version_metadata(spec_version, Some(height)).await
+    }
+
+    /// The metadata of the latest runtime, whose events are the `ChainXEvent`.
+    pub async fn current_metadata(&self) -> Result<Arc<Metadata>> {
+        let known = *self
+            .current_spec_version
+            .lock()
+            .expect("current spec version lock poisoned; qed");
+        let spec_version = match known {
+            Some(spec_version) => spec_version,
+            None => {
+                let spec_version = self.runtime_version(None).await?.spec_version;
+                *self
+                    .current_spec_version
+                    .lock()
+                    .expect("current spec version lock poisoned; qed") = Some(spec_version);
+                spec_version
+            }
+        };
+        self.spec_version_metadata(spec_version, None).await
+    }
+
+    /// The metadata of the runtime of the spec version, which is fetched at the block of that
+    /// version (the best block if `None`) unless it's known.
+    pub async fn spec_version_metadata(
+        &self,
+        spec_version: u32,
+        height: Option<BlockNumber>,
+    ) -> Result<Arc<Metadata>> {
+        if let Some(metadata) = self
+            .metadata
+            .lock()
+            .expect("metadata lock poisoned; qed")
+            .get(&spec_version)
+        {
+            return Ok(metadata.clone());
+        }
+        let hash = match height {
+            Some(height) => Some(
+                self.block_hash(Some(height))
+                    .await?
+                    .ok_or_else(|| anyhow!("Block #{} not found", height))?,
+            ),
+            None => None,
+        };
+        let metadata = Arc::new(self.metadata(hash).await?);
+        log::info!(
+            "Spec Version {}, Metadata V{} with {} modules",
+            spec_version,
+            metadata.version,
+            metadata.modules.len()
+        );
+        self.metadata
+            .lock()
+            .expect("metadata lock poisoned; qed")
+            .insert(spec_version, metadata.clone());
+        Ok(metadata)
+    }
+
+    /// Decode the events of the block with the metadata of its runtime into JSON values,
+    /// instead of the hand-written `ChainXEvent`.
+    ///
+    /// The records are decoded with or without the topics by the layout of the spec version,
+    /// and the alternative layouts.
+    pub async fn dynamic_events(&self, height: BlockNumber) -> Result<Option<Vec<Value>>> {
+        let storage = match self.system_events(height).await? {
+            Some(storage) => storage,
+            None => return Ok(None),
+        };
+        let spec_version = self.spec_version(height).await?;
+        let metadata = self.runtime_metadata(height).await?;
+        let decoders = EventsDecoder::with_alternatives(spec_version);
+        let (_, events) = decode_dynamic_events(&self.registry, &metadata, &decoders, &storage.0)?;
+        Ok(Some(events))
+    }
+
+    /// Decode a call with the metadata of the runtime at the block into a JSON value.
+    pub async fn dynamic_call(&self, height: BlockNumber, call: &[u8]) -> Result<Value> {
+        let metadata = self.runtime_metadata(height).await?;
+        self.registry.decode_call(&metadata, &mut &call[..])
+    }
+
+    /// The extrinsics of the block fetched by `chain_getBlock`, with their results in the
+    /// events of the block.
+    pub async fn block_extrinsics(&self, height: BlockNumber) -> Result<Vec<BlockExtrinsic>> {
+        let hash = self
+            .block_hash(Some(height))
+            .await?
+            .ok_or_else(|| anyhow!("Block #{} not found", height))?;
+        let block = self
+            .block(Some(hash))
+            .await?
+            .ok_or_else(|| anyhow!("Block #{} not found", height))?;
+
+        // The extrinsics whose results are in the undecodable events are unknown.
+        let key = StorageKey(twox_128(b"System Events").to_vec());
+        let results = match self.storage(&key, Some(hash)).await? {
+            Some(storage) => {
+                // The `System` events have no arguments in every runtime, so they are never skipped.
+                match self.decode_block_events(height, &storage.0).await? {
+                    Ok(events) => extrinsic_results(&events.records),
+                    Err(err) => {
+                        log::error!("Block Height {}, err: {}", height, err);
+                        BTreeMap::new()
+                    }
+                }
+            }
+            None => BTreeMap::new(),
+        };
+
+        block
+            .block
+            .extrinsics
+            .iter()
+            .enumerate()
+            .map(|(index, data)| {
+                let index = index as u32;
+                let extrinsic = UncheckedExtrinsic::decode_from(&data.0).map_err(|err| {
+                    anyhow!("Block Height {}, extrinsic {}: {}", height, index, err)
+                })?;
+                let success = results.get(&index).copied();
+                Ok(BlockExtrinsic {
+                    index,
+                    extrinsic,
+                    success,
+                })
+            })
+            .collect()
+    }
+
     pub async fn total_nodes_vote_weight_v1(
         &self,
         hash: Option<Hash>,
@@ -421,6 +803,14 @@ impl ChainX {
     }
 }
 
+async fn connect_websocket(url: &Url, retry: RetryConfig) -> Result<WsClient> {
+    assert!(
+        url.as_str().starts_with("ws://") || url.as_str().starts_with("wss://"),
+        "the url accepts websocket protocol only"
+    );
+    WsClient::new(url, retry).await
+}
+
 /*
 fn calc_node_vote_weight(intention: &IntentionInfo, height: BlockNumber) -> u64 {
     let last_total_vote_weight = intention.intention_profs.last_total_vote_weight;
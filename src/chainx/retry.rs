@@ -0,0 +1,201 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+use std::{fmt, future::Future, sync::Arc, time::Duration};
+
+use anyhow::Result;
+use async_std::sync::RwLock;
+use serde::Deserialize;
+use serde_json::Value;
+use url::Url;
+use web3::{transports::WebSocket, BatchTransport, Transport};
+
+/// The retry policy of the rpc requests.
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case", default)]
+pub struct RetryConfig {
+    /// Max number of retries of a single request before giving up.
+    pub max_retries: u32,
+    /// The delay (in milliseconds) before the first retry, doubled on each later retry.
+    pub initial_backoff_ms: u64,
+    /// The upper bound (in milliseconds) of the delay between two retries.
+    pub max_backoff_ms: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 10,
+            initial_backoff_ms: 500,
+            max_backoff_ms: 30_000,
+        }
+    }
+}
+
+impl RetryConfig {
+    /// The delay before the `attempt`-th retry (starts from 1).
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 1u64
+            .checked_shl(attempt.saturating_sub(1))
+            .unwrap_or(u64::MAX);
+        let delay = self.initial_backoff_ms.saturating_mul(factor);
+        Duration::from_millis(delay.min(self.max_backoff_ms))
+    }
+}
+
+/// The error of rpc requests that can't be recovered by retrying.
+#[derive(Debug)]
+pub enum RpcError {
+    /// The request still failed after the retry budget was exhausted.
+    RetryExhausted {
+        method: String,
+        retries: u32,
+        source: web3::Error,
+    },
+}
+
+impl fmt::Display for RpcError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RpcError::RetryExhausted {
+                method,
+                retries,
+                source,
+            } => write!(
+                f,
+                "rpc `{}` still failed after {} retries: {}",
+                method, retries, source
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RpcError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            RpcError::RetryExhausted { source, .. } => Some(source),
+        }
+    }
+}
+
+/// Only the transport errors are worth retrying, the rpc errors (like method not found)
+/// are returned by the node and will be the same next time.
+fn is_retryable(err: &web3::Error) -> bool {
+    !matches!(err, web3::Error::Rpc(_) | web3::Error::Decoder(_))
+}
+
+/// The websocket connection to a ChainX node, which reconnects and retries the requests
+/// when the connection is broken.
+#[derive(Clone)]
+pub(crate) struct WsClient {
+    url: Url,
+    client: Arc<RwLock<WebSocket>>,
+    retry: RetryConfig,
+}
+
+impl WsClient {
+    pub(crate) async fn new(url: &Url, retry: RetryConfig) -> Result<Self> {
+        let client = WebSocket::new(url.as_str()).await?;
+        Ok(Self {
+            url: url.clone(),
+            client: Arc::new(RwLock::new(client)),
+            retry,
+        })
+    }
+
+    /// Execute a rpc request, reconnect and retry it when the connection is broken.
+    pub(crate) async fn execute(&self, method: &str, params: Vec<Value>) -> Result<Value> {
+        self.with_retries(method, move |client| {
+            let params = params.clone();
+            async move { client.execute(method, params).await }
+        })
+        .await
+    }
+
+    /// Execute a batch of requests of the same rpc method, the whole batch will be retried
+    /// when the connection is broken.
+    pub(crate) async fn execute_batch(
+        &self,
+        method: &str,
+        batch: Vec<Vec<Value>>,
+    ) -> Result<Vec<Result<Value>>> {
+        let responses = self
+            .with_retries(method, move |client| {
+                let requests = batch
+                    .iter()
+                    .map(|params| client.prepare(method, params.clone()))
+                    .collect::<Vec<_>>();
+                async move { client.send_batch(requests).await }
+            })
+            .await?;
+        Ok(responses
+            .into_iter()
+            .map(|response| response.map_err(anyhow::Error::from))
+            .collect())
+    }
+
+    async fn with_retries<T, F, Fut>(&self, method: &str, f: F) -> Result<T>
+    where
+        F: Fn(WebSocket) -> Fut,
+        Fut: Future<Output = web3::Result<T>>,
+    {
+        let mut retries = 0;
+        loop {
+            let client = self.client.read().await.clone();
+            let err = match f(client).await {
+                Ok(value) => return Ok(value),
+                Err(err) => err,
+            };
+            if !is_retryable(&err) {
+                return Err(err.into());
+            }
+            if retries >= self.retry.max_retries {
+                return Err(RpcError::RetryExhausted {
+                    method: method.to_string(),
+                    retries,
+                    source: err,
+                }
+                .into());
+            }
+
+            retries += 1;
+            let backoff = self.retry.backoff(retries);
+            log::warn!(
+                "Rpc `{}` failed: {}, retry ({} / {}) after {:?}",
+                method,
+                err,
+                retries,
+                self.retry.max_retries,
+                backoff
+            );
+            async_std::task::sleep(backoff).await;
+            self.reconnect().await;
+        }
+    }
+
+    /// Replace the underlying connection, the failure will be left to the next retry.
+    async fn reconnect(&self) {
+        match WebSocket::new(self.url.as_str()).await {
+            Ok(client) => *self.client.write().await = client,
+            Err(err) => log::warn!("Reconnect to {} failed: {}", self.url, err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff() {
+        let retry = RetryConfig {
+            max_retries: 10,
+            initial_backoff_ms: 500,
+            max_backoff_ms: 3_000,
+        };
+        assert_eq!(retry.backoff(1), Duration::from_millis(500));
+        assert_eq!(retry.backoff(2), Duration::from_millis(1_000));
+        assert_eq!(retry.backoff(3), Duration::from_millis(2_000));
+        assert_eq!(retry.backoff(4), Duration::from_millis(3_000));
+        assert_eq!(retry.backoff(100), Duration::from_millis(3_000));
+    }
+}
@@ -8,22 +8,34 @@ use sp_core::{
     crypto::UncheckedFrom,
     ed25519,
     storage::{StorageData, StorageKey},
-    twox_128, Hasher,
+    twox_128, Bytes, Hasher,
 };
 use sp_runtime::traits::BlakeTwo256;
-use web3::{BatchTransport, Transport};
 
-use crate::chainx::{decode::CompatibleBTreeMap, types::*, ChainX};
+use crate::chainx::{
+    decode::{AddrInfo, CompatibleBTreeMap, PendingState},
+    metadata::Metadata,
+    types::*,
+    ChainX,
+};
 
 impl ChainX {
     /// Get a block hash, returns hash of latest block by default
     pub async fn block_hash(&self, block_number: Option<BlockNumber>) -> Result<Option<Hash>> {
         let params = vec![to_json_value(block_number)?];
-        let hash = self.client.execute("chain_getBlockHash", params).await?;
+        let hash = self.request("chain_getBlockHash", params).await?;
         let hash = from_json_value(hash)?;
         Ok(hash)
     }
 
+    /// Get a block by its hash, returns the latest block by default
+    pub async fn block(&self, hash: Option<Hash>) -> Result<Option<SignedBlock>> {
+        let params = vec![to_json_value(hash)?];
+        let block = self.request("chain_getBlock", params).await?;
+        let block = from_json_value(block)?;
+        Ok(block)
+    }
+
     /// Fetch a storage data by key
     pub async fn storage(
         &self,
@@ -31,12 +43,29 @@ impl ChainX {
         hash: Option<Hash>,
     ) -> Result<Option<StorageData>> {
         let params = vec![to_json_value(key)?, to_json_value(hash)?];
-        let data = self.client.execute("state_getStorage", params).await?;
+        let data = self.request("state_getStorage", params).await?;
         let data = from_json_value(data)?;
         log::debug!("state_getStorage {:?}", data);
         Ok(data)
     }
 
+    /// Get the runtime version at a block, returns the version of latest block by default
+    pub async fn runtime_version(&self, hash: Option<Hash>) -> Result<RuntimeVersion> {
+        let params = vec![to_json_value(hash)?];
+        let version = self.request("state_getRuntimeVersion", params).await?;
+        let version = from_json_value(version)?;
+        log::debug!("state_getRuntimeVersion {:?}", version);
+        Ok(version)
+    }
+
+    /// Get the runtime metadata at a block, returns the metadata of latest block by default
+    pub async fn metadata(&self, hash: Option<Hash>) -> Result<Metadata> {
+        let params = vec![to_json_value(hash)?];
+        let data = self.request("state_getMetadata", params).await?;
+        let data: Bytes = from_json_value(data)?;
+        Metadata::parse(&data.0)
+    }
+
     pub async fn session_index(&self, hash: Option<Hash>) -> Result<Option<BlockNumber>> {
         let hashed_key = twox_128(b"Session CurrentIndex").to_vec();
         if let Some(data) = self.storage(&StorageKey(hashed_key), hash).await? {
@@ -48,6 +77,30 @@ impl ChainX {
         }
     }
 
+    /// 下一个账户索引集合
+    pub async fn next_enum_set(&self, hash: Option<Hash>) -> Result<Option<AccountIndex>> {
+        let hashed_key = twox_128(b"Indices NextEnumSet").to_vec();
+        match self.storage(&StorageKey(hashed_key), hash).await? {
+            Some(data) => Ok(Some(Decode::decode(&mut data.0.as_slice())?)),
+            None => Ok(None),
+        }
+    }
+
+    /// 账户索引集合
+    pub async fn enum_set(
+        &self,
+        set_index: AccountIndex,
+        hash: Option<Hash>,
+    ) -> Result<Option<Vec<AccountId>>> {
+        let mut unhashed_key = b"Indices EnumSet".to_vec();
+        Encode::encode_to(&set_index, &mut unhashed_key);
+        let hashed_key = blake2_256(&unhashed_key).to_vec();
+        match self.storage(&StorageKey(hashed_key), hash).await? {
+            Some(data) => Ok(Some(Decode::decode(&mut data.0.as_slice())?)),
+            None => Ok(None),
+        }
+    }
+
     /// 获取用户资产信息
     pub async fn asset(
         &self,
@@ -62,10 +115,7 @@ impl ChainX {
             to_json_value(size)?,
             to_json_value(hash)?,
         ];
-        let data = self
-            .client
-            .execute("chainx_getAssetsByAccount", params)
-            .await?;
+        let data = self.request("chainx_getAssetsByAccount", params).await?;
         let data = from_json_value(data)?;
         log::debug!("chainx_getAssetsByAccount {:?}", data);
         Ok(data)
@@ -87,10 +137,11 @@ impl ChainX {
                 to_json_value(size)?,
                 to_json_value(hash)?,
             ];
-            let request = self.client.prepare("chainx_getAssetsByAccount", params);
-            requests.push(request);
+            requests.push(params);
         }
-        let responses = self.client.send_batch(requests).await?;
+        let responses = self
+            .request_batch("chainx_getAssetsByAccount", requests)
+            .await?;
 
         let mut data = Vec::with_capacity(responses.len());
         for response in responses {
@@ -113,12 +164,21 @@ impl ChainX {
             to_json_value(size)?,
             to_json_value(hash)?,
         ];
-        let data = self.client.execute("chainx_getAssets", params).await?;
+        let data = self.request("chainx_getAssets", params).await?;
         let data = from_json_value(data)?;
         log::debug!("chainx_getAssets {:?}", data);
         Ok(data)
     }
 
+    /// 获取交易对信息
+    pub async fn trading_pairs(&self, hash: Option<Hash>) -> Result<Option<Vec<TradingPair>>> {
+        let params = vec![to_json_value(hash)?];
+        let data = self.request("chainx_getTradingPairs", params).await?;
+        let data = from_json_value(data)?;
+        log::debug!("chainx_getTradingPairs {:?}", data);
+        Ok(data)
+    }
+
     /// 获取单个节点信息
     pub async fn intention(
         &self,
@@ -126,10 +186,7 @@ impl ChainX {
         hash: Option<Hash>,
     ) -> Result<Option<IntentionInfo>> {
         let params = vec![to_json_value(who)?, to_json_value(hash)?];
-        let data = self
-            .client
-            .execute("chainx_getIntentionByAccount", params)
-            .await?;
+        let data = self.request("chainx_getIntentionByAccount", params).await?;
         let data = from_json_value(data)?;
         log::debug!("chainx_getIntentionByAccount {:?}", data);
         Ok(data)
@@ -143,8 +200,7 @@ impl ChainX {
     ) -> Result<Option<IntentionInfoV1>> {
         let params = vec![to_json_value(who)?, to_json_value(hash)?];
         let data = self
-            .client
-            .execute("chainx_getIntentionByAccountV1", params)
+            .request("chainx_getIntentionByAccountV1", params)
             .await?;
         let data = from_json_value(data)?;
         log::debug!("chainx_getIntentionByAccountV1 {:?}", data);
@@ -154,7 +210,7 @@ impl ChainX {
     /// 获取节点列表
     pub async fn intentions(&self, hash: Option<Hash>) -> Result<Option<Vec<IntentionInfo>>> {
         let params = vec![to_json_value(hash)?];
-        let data = self.client.execute("chainx_getIntentions", params).await?;
+        let data = self.request("chainx_getIntentions", params).await?;
         let data = from_json_value(data)?;
         log::debug!("chainx_getIntentions {:?}", data);
         Ok(data)
@@ -163,10 +219,7 @@ impl ChainX {
     /// 获取节点列表
     pub async fn intentions_v1(&self, hash: Option<Hash>) -> Result<Option<Vec<IntentionInfoV1>>> {
         let params = vec![to_json_value(hash)?];
-        let data = self
-            .client
-            .execute("chainx_getIntentionsV1", params)
-            .await?;
+        let data = self.request("chainx_getIntentionsV1", params).await?;
         let data = from_json_value(data)?;
         log::debug!("chainx_getIntentionsV1 {:?}", data);
         Ok(data)
@@ -179,10 +232,7 @@ impl ChainX {
         hash: Option<Hash>,
     ) -> Result<Option<Vec<(AccountId, NominationRecord)>>> {
         let params = vec![to_json_value(who)?, to_json_value(hash)?];
-        let data = self
-            .client
-            .execute("chainx_getNominationRecords", params)
-            .await?;
+        let data = self.request("chainx_getNominationRecords", params).await?;
         let data = from_json_value(data)?;
         log::debug!("chainx_getNominationRecords {:?}", data);
         Ok(data)
@@ -196,8 +246,7 @@ impl ChainX {
     ) -> Result<Option<Vec<(AccountId, NominationRecordV1)>>> {
         let params = vec![to_json_value(who)?, to_json_value(hash)?];
         let data = self
-            .client
-            .execute("chainx_getNominationRecordsV1", params)
+            .request("chainx_getNominationRecordsV1", params)
             .await?;
         let data = from_json_value(data)?;
         log::debug!("chainx_getNominationRecordsV1 {:?}", data);
@@ -213,10 +262,11 @@ impl ChainX {
         let mut requests = Vec::with_capacity(accounts.len());
         for account in accounts {
             let params = vec![to_json_value(account)?, to_json_value(hash)?];
-            let request = self.client.prepare("chainx_getNominationRecordsV1", params);
-            requests.push(request);
+            requests.push(params);
         }
-        let responses = self.client.send_batch(requests).await?;
+        let responses = self
+            .request_batch("chainx_getNominationRecordsV1", requests)
+            .await?;
 
         let mut data = Vec::with_capacity(responses.len());
         for response in responses {
@@ -227,16 +277,44 @@ impl ChainX {
         Ok(data)
     }
 
+    /// 多签地址信息
+    pub async fn multisig_addr_info(
+        &self,
+        multisig: &AccountId,
+        hash: Option<Hash>,
+    ) -> Result<Option<AddrInfo<AccountId>>> {
+        let mut unhashed_key = b"XMultiSig MultiSigAddrInfo".to_vec();
+        Encode::encode_to(multisig, &mut unhashed_key);
+        let hashed_key = blake2_256(&unhashed_key).to_vec();
+        match self.storage(&StorageKey(hashed_key), hash).await? {
+            Some(data) => Ok(Some(Decode::decode(&mut data.0.as_slice())?)),
+            None => Ok(None),
+        }
+    }
+
+    /// 多签待确认提案
+    pub async fn multisig_pending_state(
+        &self,
+        multisig: &AccountId,
+        proposal_id: &Hash,
+        hash: Option<Hash>,
+    ) -> Result<Option<PendingState>> {
+        let mut unhashed_key = b"XMultiSig PendingStateFor".to_vec();
+        Encode::encode_to(&(multisig, proposal_id), &mut unhashed_key);
+        let hashed_key = blake2_256(&unhashed_key).to_vec();
+        match self.storage(&StorageKey(hashed_key), hash).await? {
+            Some(data) => Ok(Some(PendingState::decode_raw(&data.0)?)),
+            None => Ok(None),
+        }
+    }
+
     /// 充值挖矿列表
     pub async fn psedu_intentions(
         &self,
         hash: Option<Hash>,
     ) -> Result<Option<Vec<PseduIntentionInfo>>> {
         let params = vec![to_json_value(hash)?];
-        let data = self
-            .client
-            .execute("chainx_getPseduIntentions", params)
-            .await?;
+        let data = self.request("chainx_getPseduIntentions", params).await?;
         let data = from_json_value(data)?;
         log::debug!("chainx_getPseduIntentions {:?}", data);
         Ok(data)
@@ -248,10 +326,7 @@ impl ChainX {
         hash: Option<Hash>,
     ) -> Result<Option<Vec<PseduIntentionInfoV1>>> {
         let params = vec![to_json_value(hash)?];
-        let data = self
-            .client
-            .execute("chainx_getPseduIntentionsV1", params)
-            .await?;
+        let data = self.request("chainx_getPseduIntentionsV1", params).await?;
         let data = from_json_value(data)?;
         log::debug!("chainx_getPseduIntentionsV1 {:?}", data);
         Ok(data)
@@ -359,8 +434,7 @@ impl ChainX {
     ) -> Result<Option<Vec<PseduNominationRecord>>> {
         let params = vec![to_json_value(who)?, to_json_value(hash)?];
         let data = self
-            .client
-            .execute("chainx_getPseduNominationRecords", params)
+            .request("chainx_getPseduNominationRecords", params)
             .await?;
         let data = from_json_value(data)?;
         log::debug!("chainx_getPseduNominationRecords {:?}", data);
@@ -375,8 +449,7 @@ impl ChainX {
     ) -> Result<Option<Vec<PseduNominationRecordV1>>> {
         let params = vec![to_json_value(who)?, to_json_value(hash)?];
         let data = self
-            .client
-            .execute("chainx_getPseduNominationRecordsV1", params)
+            .request("chainx_getPseduNominationRecordsV1", params)
             .await?;
         let data = from_json_value(data)?;
         log::debug!("chainx_getPseduNominationRecordsV1 {:?}", data);
@@ -392,12 +465,11 @@ impl ChainX {
         let mut requests = Vec::with_capacity(accounts.len());
         for account in accounts {
             let params = vec![to_json_value(account)?, to_json_value(hash)?];
-            let request = self
-                .client
-                .prepare("chainx_getPseduNominationRecordsV1", params);
-            requests.push(request);
+            requests.push(params);
         }
-        let responses = self.client.send_batch(requests).await?;
+        let responses = self
+            .request_batch("chainx_getPseduNominationRecordsV1", requests)
+            .await?;
 
         let mut data = Vec::with_capacity(responses.len());
         for response in responses {
@@ -5,7 +5,7 @@ use std::collections::BTreeMap;
 
 use codec::{Decode, Encode};
 use serde::{Deserialize, Serialize};
-use sp_core::H256;
+use sp_core::{Bytes, H256};
 
 pub type AccountId = H256;
 pub type AccountIndex = u32;
@@ -13,6 +13,8 @@ pub type Address = pallet_indices::address::Address<AccountId, AccountIndex>;
 
 pub type Hash = H256;
 pub type BlockNumber = u64;
+pub type Index = u64;
+pub type Acceleration = u32;
 pub type Balance = u64;
 pub type SessionKey = sp_core::ed25519::Public;
 pub type Precision = u16;
@@ -53,6 +55,20 @@ pub struct TotalAssetInfo {
     pub limit_props: BTreeMap<AssetLimit, bool>,
 }
 
+/// The trading pair of `chainx_getTradingPairs`, the prices are ignored.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TradingPair {
+    pub id: u32,
+    /// The base token.
+    pub assets: String,
+    /// The quote token.
+    pub currency: String,
+    pub precision: u32,
+    pub unit_precision: u32,
+    pub online: bool,
+}
+
 #[derive(
     PartialEq, PartialOrd, Ord, Eq, Clone, Copy, Debug, Encode, Decode, Serialize, Deserialize,
 )]
@@ -347,3 +363,25 @@ pub struct RawDepositVoteWeight<BlockNumber: Default> {
     pub last_deposit_weight: u64,
     pub last_deposit_weight_update: BlockNumber,
 }
+
+/// The runtime version returned by `state_getRuntimeVersion`, the `apis` are ignored.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RuntimeVersion {
+    pub spec_name: String,
+    pub impl_name: String,
+    pub authoring_version: u32,
+    pub spec_version: u32,
+    pub impl_version: u32,
+}
+
+/// The block returned by `chain_getBlock`, only the extrinsics are needed.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SignedBlock {
+    pub block: Block,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Block {
+    pub extrinsics: Vec<Bytes>,
+}
@@ -1,12 +1,15 @@
 // Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
 
-use std::{fs::File, path::PathBuf};
+use std::{collections::BTreeMap, fs::File, path::PathBuf};
 
 use anyhow::Result;
 use serde::Deserialize;
 use structopt::StructOpt;
 use url::Url;
 
+use crate::chainx::{CassetteConfig, RetryConfig, TypeDef};
+use crate::export::Stage;
+
 #[derive(Clone, Debug, StructOpt)]
 #[structopt(
     name = "chainx-state-exporter",
@@ -16,6 +19,143 @@ use url::Url;
 pub struct CmdConfig {
     #[structopt(short, long, value_name = "FILE", default_value = "config.json")]
     pub config: PathBuf,
+    /// The block height of ChainX, overrides the `height` of config.
+    #[structopt(long, value_name = "HEIGHT")]
+    pub height: Option<u64>,
+    /// ChainX WebSocket url, overrides the `chainx-ws-url` of config.
+    #[structopt(long, value_name = "URL")]
+    pub url: Option<Url>,
+    #[structopt(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Clone, Debug, StructOpt)]
+pub enum Command {
+    /// Export the state with its dependencies, the stages whose outputs already exist are skipped.
+    Export {
+        /// Export the state again even if it already exists.
+        #[structopt(long)]
+        force: bool,
+        #[structopt(subcommand)]
+        target: ExportTarget,
+    },
+    /// Verify the exported state, the missing state will be exported first.
+    Verify {
+        #[structopt(subcommand)]
+        target: VerifyTarget,
+    },
+    /// Re-fetch the blocks in `accounts/missing.log` and recover the accounts created in them.
+    RetryMissing,
+}
+
+#[derive(Clone, Copy, Debug, StructOpt)]
+pub enum ExportTarget {
+    /// The current session index.
+    SessionIndex,
+    /// All accounts.
+    Accounts,
+    /// The account indices of all indexed accounts.
+    AccountIndices,
+    /// All intentions.
+    Intentions,
+    /// The assets of all accounts.
+    Assets,
+    /// The deposit weight of all accounts and psedu intentions.
+    DepositWeight,
+    /// The vote weight of all accounts and intentions.
+    VoteWeight,
+    /// All the above, including the verifications.
+    All,
+    /// The signed extrinsics of all blocks, saved in `extrinsics/`.
+    Extrinsics,
+    /// The event records of all blocks, saved in `events/`.
+    Events,
+    /// All withdrawal applications, including the pending ones to be refunded.
+    Withdrawals,
+    /// The X-BTC deposit ledger of all accounts.
+    BtcDeposits,
+    /// The accounts and referral channels bound to the bitcoin and ethereum addresses.
+    AddressBindings,
+    /// All bitcoin trustee sessions, including the current trustee set.
+    TrusteeSessions,
+    /// The referral channels of all accounts for each token.
+    ChannelBindings,
+    /// The open DEX orders of all accounts and their reserved balances.
+    DexOrders,
+    /// All SDOT claims, reconciled against the total SDOT.
+    SdotClaims,
+    /// The still-locked L-BTC outputs.
+    LbtcLockups,
+    /// All multisig addresses with their owners and pending proposals.
+    Multisigs,
+    /// The slashes, missed blocks and forced inactivations of all validators.
+    ValidatorOffences,
+    /// The timeline of all sessions with their validators and rewards.
+    Sessions,
+    /// The lifetime rewards claimed by all accounts.
+    Rewards,
+}
+
+#[derive(Clone, Copy, Debug, StructOpt)]
+pub enum VerifyTarget {
+    /// Verify the assets of accounts against the total assets.
+    Assets,
+    /// Verify the vote weight of accounts against the intentions.
+    VoteWeight,
+    /// Verify the X-BTC deposit ledger against the assets of accounts.
+    BtcDeposits,
+    /// Verify the open DEX orders against the reserved balances of accounts.
+    DexOrders,
+    /// Verify the still-locked L-BTC outputs against the L-BTC of accounts.
+    LbtcLockups,
+}
+
+impl Command {
+    /// The target stages of the command.
+    pub fn stages(&self) -> Vec<Stage> {
+        match self {
+            Command::Export { target, .. } => match target {
+                ExportTarget::SessionIndex => vec![Stage::SessionIndex],
+                ExportTarget::Accounts => vec![Stage::Accounts],
+                ExportTarget::AccountIndices => vec![Stage::AccountIndices],
+                ExportTarget::Intentions => vec![Stage::Intentions],
+                ExportTarget::Assets => vec![Stage::Assets],
+                ExportTarget::DepositWeight => vec![Stage::DepositWeight],
+                ExportTarget::VoteWeight => vec![Stage::VoteWeight],
+                ExportTarget::All => Stage::ALL.to_vec(),
+                ExportTarget::Extrinsics => vec![Stage::Extrinsics],
+                ExportTarget::Events => vec![Stage::Events],
+                ExportTarget::Withdrawals => vec![Stage::Withdrawals],
+                ExportTarget::BtcDeposits => vec![Stage::BtcDeposits],
+                ExportTarget::AddressBindings => vec![Stage::AddressBindings],
+                ExportTarget::TrusteeSessions => vec![Stage::TrusteeSessions],
+                ExportTarget::ChannelBindings => vec![Stage::ChannelBindings],
+                ExportTarget::DexOrders => vec![Stage::DexOrders],
+                ExportTarget::SdotClaims => vec![Stage::SdotClaims],
+                ExportTarget::LbtcLockups => vec![Stage::LbtcLockups],
+                ExportTarget::Multisigs => vec![Stage::Multisigs],
+                ExportTarget::ValidatorOffences => vec![Stage::ValidatorOffences],
+                ExportTarget::Sessions => vec![Stage::Sessions],
+                ExportTarget::Rewards => vec![Stage::Rewards],
+            },
+            Command::Verify { target } => match target {
+                VerifyTarget::Assets => vec![Stage::VerifyAssets],
+                VerifyTarget::VoteWeight => vec![Stage::VerifyVoteWeight],
+                VerifyTarget::BtcDeposits => vec![Stage::VerifyBtcDeposits],
+                VerifyTarget::DexOrders => vec![Stage::VerifyDexOrders],
+                VerifyTarget::LbtcLockups => vec![Stage::VerifyLbtcLockups],
+            },
+            Command::RetryMissing => vec![],
+        }
+    }
+
+    /// Whether to run the target stages even if their outputs already exist.
+    pub fn force(&self) -> bool {
+        match self {
+            Command::Export { force, .. } => *force,
+            Command::Verify { .. } | Command::RetryMissing => false,
+        }
+    }
 }
 
 #[derive(Clone, Debug, Deserialize)]
@@ -25,13 +165,29 @@ pub struct Config {
     pub chainx_ws_url: Url,
     /// The block height of ChainX.
     pub height: u64,
+    /// The retry policy of the rpc requests.
+    #[serde(default)]
+    pub retry: RetryConfig,
+    /// Record the rpc responses into the cassette, or replay them from the cassette.
+    #[serde(default)]
+    pub cassette: Option<CassetteConfig>,
+    /// The types used by the metadata driven decoder besides the ChainX 1.0 types.
+    #[serde(default)]
+    pub types: BTreeMap<String, TypeDef>,
 }
 
 impl CmdConfig {
     /// Generate config from command.
-    pub fn init() -> Result<Config> {
+    pub fn init() -> Result<(Command, Config)> {
         let cmd: CmdConfig = CmdConfig::from_args();
         let file = File::open(cmd.config)?;
-        Ok(serde_json::from_reader(file)?)
+        let mut config: Config = serde_json::from_reader(file)?;
+        if let Some(height) = cmd.height {
+            config.height = height;
+        }
+        if let Some(url) = cmd.url {
+            config.chainx_ws_url = url;
+        }
+        Ok((cmd.command, config))
     }
 }
@@ -0,0 +1,402 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+
+use super::chunk::{
+    chunk_filename, chunks, full_chunk_filename, parse_chunk_filename, CHUNK_NUMBER,
+};
+use crate::{
+    accounts_exists, accounts_filenames, created_accounts, load_accounts,
+    load_missing_block_heights, load_state, log_skipped_events, save_accounts,
+    save_missing_block_heights, save_state, AccountId, AccountIndex, BlockNumber, ChainX, Config,
+    DecodedEvents,
+};
+
+#[derive(Ord, PartialOrd, Eq, PartialEq, Clone, Debug, Serialize, Deserialize)]
+struct NewAccount {
+    height: u64,
+    account: AccountId,
+}
+
+/// All accounts created up to the high-water mark, so that a later export only needs to scan
+/// the new blocks.
+#[derive(Debug, Serialize, Deserialize)]
+struct AccountsIndex {
+    /// The highest block that has been scanned.
+    height: BlockNumber,
+    /// Sorted by the account.
+    accounts: Vec<NewAccount>,
+}
+
+impl AccountsIndex {
+    fn merge(&mut self, height: BlockNumber, new_accounts: Vec<NewAccount>) {
+        self.accounts.extend(new_accounts);
+        self.accounts
+            .sort_unstable_by(|a, b| (a.account, a.height).cmp(&(b.account, b.height)));
+        self.accounts
+            .dedup_by_key(|new_account| new_account.account);
+        self.height = height;
+    }
+}
+
+const INDEX: &str = "index.json";
+
+/// The number of the accounts in an `Indices EnumSet`.
+const ENUM_SET_SIZE: AccountIndex = 64;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(super) struct AccountWithIndex {
+    pub index: AccountIndex,
+    pub account: AccountId,
+}
+
+pub async fn export(conf: &Config) -> Result<()> {
+    let target_height = conf.height;
+
+    // Save accounts from block #0
+    if accounts_exists("genesis.json")? {
+        log::info!("Accounts (Block #0) already got");
+    } else {
+        let chainx = ChainX::connect(conf).await?;
+        let accounts = chainx.new_account(0, vec![0]).await?;
+        let mut accounts = accounts
+            .into_iter()
+            .map(|(height, account)| NewAccount { height, account })
+            .collect::<Vec<_>>();
+        accounts.sort_unstable();
+        save_accounts("genesis.json", &accounts)?;
+    }
+
+    let mut index = if accounts_exists(INDEX)? {
+        load_accounts::<_, AccountsIndex>(INDEX)?
+    } else {
+        AccountsIndex {
+            height: 0,
+            accounts: load_accounts("genesis.json")?,
+        }
+    };
+    log::info!("Accounts already scanned up to Block #{}", index.height);
+
+    if target_height > index.height {
+        let chunks = chunks(index.height + 1, target_height).collect::<Vec<_>>();
+        for &(begin, end) in &chunks {
+            if chunk_exists(begin, end)? {
+                log::info!("Accounts {}-{} already got", begin, end);
+                continue;
+            }
+
+            // Each connection handles 1/50 of the total blocks, and 50 connections are required
+            const CONNECTION_NUM: u64 = 50;
+            let chunk_size = CHUNK_NUMBER / CONNECTION_NUM;
+
+            let heights = (begin..=end).collect::<Vec<_>>();
+
+            let mut handles = vec![];
+            for (id, chunk_heights) in heights.chunks(chunk_size as usize).enumerate() {
+                let chainx = ChainX::connect(conf).await?;
+                let heights = chunk_heights.iter().copied().collect::<Vec<u64>>();
+                let handle =
+                    async_std::task::spawn(async move { chainx.new_account(id, heights).await });
+                handles.push((id, handle));
+            }
+
+            let mut total_new_accounts = vec![];
+            for (id, handle) in handles {
+                let accounts = handle.await?;
+                let mut new_accounts = accounts
+                    .into_iter()
+                    .map(|(height, account)| NewAccount { height, account })
+                    .collect::<Vec<_>>();
+                log::info!(
+                    "[{}] Connection Finished, New Account Number: {}",
+                    id,
+                    new_accounts.len()
+                );
+
+                new_accounts.sort_unstable();
+                total_new_accounts.extend(new_accounts);
+            }
+
+            total_new_accounts.sort_unstable();
+            save_accounts(chunk_filename(begin, end), &total_new_accounts)?;
+            log::info!("Total New Account Number: {}", total_new_accounts.len());
+        }
+
+        let mut new_accounts = vec![];
+        for (begin, end) in chunks {
+            new_accounts.extend(load_chunk(begin, end)?);
+        }
+        log::info!(
+            "New Account Number of Block #{}-#{}: {}",
+            index.height + 1,
+            target_height,
+            new_accounts.len()
+        );
+        index.merge(target_height, new_accounts);
+        save_accounts(INDEX, &index)?;
+    }
+
+    // collect needed accounts for querying other states
+    let total_accounts = index
+        .accounts
+        .into_iter()
+        .filter(|val| val.height <= target_height)
+        .map(|val| val.account)
+        .collect::<Vec<_>>();
+    log::info!("Total Account Number: {}", total_accounts.len());
+
+    save_state(target_height, "accounts.json", &total_accounts)?;
+
+    Ok(())
+}
+
+/// Export the `AccountIndex -> AccountId` table at the target height into
+/// `account-indices.json`, read from the `Indices EnumSet`s rather than the `NewAccountIndex`
+/// events, so the scanned chunks don't need to be scanned again.
+pub async fn export_indices(conf: &Config) -> Result<()> {
+    let height = conf.height;
+
+    let accounts: Vec<AccountId> = load_state(height, "accounts.json")?;
+
+    let chainx = ChainX::connect(conf).await?;
+    let hash = chainx.block_hash(Some(height)).await?;
+    let next_enum_set = chainx.next_enum_set(hash).await?.unwrap_or_default();
+    let mut enum_sets = vec![];
+    for set_index in 0..=next_enum_set {
+        enum_sets.push(chainx.enum_set(set_index, hash).await?.unwrap_or_default());
+    }
+    let indices = account_indices(enum_sets);
+    log::info!(
+        "Enum Set Number: {}, Account Index Number: {}",
+        next_enum_set + 1,
+        indices.len()
+    );
+
+    let unknown = indices
+        .iter()
+        .filter(|entry| accounts.binary_search(&entry.account).is_err())
+        .count();
+    if unknown > 0 {
+        log::warn!("{} indexed accounts are not in accounts.json", unknown);
+    }
+
+    save_state(height, "account-indices.json", &indices)?;
+
+    Ok(())
+}
+
+/// The index of an account is its position in all enum sets.
+fn account_indices(enum_sets: Vec<Vec<AccountId>>) -> Vec<AccountWithIndex> {
+    enum_sets
+        .into_iter()
+        .zip(0..)
+        .flat_map(|(set, set_index)| {
+            set.into_iter()
+                .zip(0..)
+                .map(move |(account, i)| AccountWithIndex {
+                    index: set_index * ENUM_SET_SIZE + i,
+                    account,
+                })
+        })
+        .collect()
+}
+
+/// Re-fetch the blocks in `accounts/missing.log` and decode their events by the metadata of their
+/// runtimes, the recovered accounts are merged into the chunk files and the accounts index.
+///
+/// The resolved heights are removed from the log, the unresolved ones are reported with their
+/// errors and kept for the next retry, so are the blocks with the skipped events.
+pub async fn retry_missing(conf: &Config) -> Result<()> {
+    let heights = load_missing_block_heights()?;
+    if heights.is_empty() {
+        log::info!("No missing block height in accounts/missing.log");
+        return Ok(());
+    }
+    log::info!("Missing Block Height Number: {}", heights.len());
+
+    let chainx = ChainX::connect(conf).await?;
+    let mut recovered = vec![];
+    let mut unresolved = vec![];
+    let mut skipped = vec![];
+    for height in heights {
+        let decoded = async {
+            match chainx.system_events(height).await? {
+                Some(storage) => chainx.decode_block_events(height, &storage.0).await,
+                None => Ok(Ok(DecodedEvents::from(vec![]))),
+            }
+        };
+        let events = match decoded.await {
+            Ok(Ok(events)) => events,
+            Ok(Err(err)) => {
+                unresolved.push((height, anyhow!("{}", err)));
+                continue;
+            }
+            Err(err) => {
+                unresolved.push((height, err));
+                continue;
+            }
+        };
+        recovered.extend(
+            created_accounts(&events.records)
+                .into_iter()
+                .map(|account| NewAccount { height, account }),
+        );
+        if !events.skipped.is_empty() {
+            skipped.push((height, events.skipped));
+        }
+    }
+    recovered.sort_unstable();
+    log::info!("Recovered New Account Number: {}", recovered.len());
+
+    for filename in accounts_filenames()? {
+        let (begin, end) = match parse_chunk_filename(&filename) {
+            Some(range) => range,
+            None => continue,
+        };
+        let accounts = recovered
+            .iter()
+            .filter(|account| begin <= account.height && account.height <= end)
+            .cloned()
+            .collect::<Vec<_>>();
+        if accounts.is_empty() {
+            continue;
+        }
+        let mut chunk: Vec<NewAccount> = load_accounts(&filename)?;
+        chunk.extend(accounts);
+        chunk.sort_unstable();
+        chunk.dedup();
+        save_accounts(&filename, &chunk)?;
+        log::info!("Accounts {} updated", filename);
+    }
+
+    if accounts_exists(INDEX)? {
+        let mut index: AccountsIndex = load_accounts(INDEX)?;
+        let height = index.height;
+        let accounts = recovered
+            .iter()
+            .filter(|account| account.height <= height)
+            .cloned()
+            .collect::<Vec<_>>();
+        if !accounts.is_empty() {
+            index.merge(height, accounts);
+            save_accounts(INDEX, &index)?;
+            log::warn!(
+                "Accounts index updated, the accounts.json exported since Block #{} should be \
+                 exported again with `--force`",
+                recovered[0].height
+            );
+        }
+    }
+
+    for (height, err) in &unresolved {
+        log::error!("Block Height {} still unresolved: {}", height, err);
+    }
+    let unresolved = unresolved
+        .into_iter()
+        .map(|(height, _)| height)
+        .collect::<Vec<_>>();
+    save_missing_block_heights(&unresolved)?;
+    log::info!("Unresolved Block Height Number: {}", unresolved.len());
+    for (height, skipped) in &skipped {
+        log::warn!("Block Height {}, skipped events {:?}", height, skipped);
+        log_skipped_events(*height, skipped)?;
+    }
+
+    Ok(())
+}
+
+/// A clamped chunk can also be taken from the full chunk containing it.
+fn chunk_exists(begin: BlockNumber, end: BlockNumber) -> Result<bool> {
+    Ok(
+        accounts_exists(chunk_filename(begin, end))?
+            || accounts_exists(full_chunk_filename(begin))?,
+    )
+}
+
+fn load_chunk(begin: BlockNumber, end: BlockNumber) -> Result<Vec<NewAccount>> {
+    if accounts_exists(chunk_filename(begin, end))? {
+        return load_accounts(chunk_filename(begin, end));
+    }
+    let accounts: Vec<NewAccount> = load_accounts(full_chunk_filename(begin))?;
+    Ok(accounts
+        .into_iter()
+        .filter(|account| begin <= account.height && account.height <= end)
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge_index() {
+        let account = |byte| AccountId::repeat_byte(byte);
+        let mut index = AccountsIndex {
+            height: 100,
+            accounts: vec![
+                NewAccount {
+                    height: 0,
+                    account: account(0x01),
+                },
+                NewAccount {
+                    height: 50,
+                    account: account(0x03),
+                },
+            ],
+        };
+        index.merge(
+            200,
+            vec![
+                NewAccount {
+                    height: 150,
+                    account: account(0x02),
+                },
+                NewAccount {
+                    height: 180,
+                    account: account(0x03),
+                },
+            ],
+        );
+        assert_eq!(index.height, 200);
+        assert_eq!(
+            index.accounts,
+            vec![
+                NewAccount {
+                    height: 0,
+                    account: account(0x01)
+                },
+                NewAccount {
+                    height: 150,
+                    account: account(0x02)
+                },
+                NewAccount {
+                    height: 50,
+                    account: account(0x03)
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_account_indices() {
+        let account = |byte| AccountId::repeat_byte(byte);
+        let mut full_set = vec![account(0x01); ENUM_SET_SIZE as usize];
+        full_set[63] = account(0x02);
+        let indices = account_indices(vec![full_set, vec![account(0x03)]]);
+        assert_eq!(indices.len(), 65);
+        assert_eq!(
+            indices[63..],
+            [
+                AccountWithIndex {
+                    index: 63,
+                    account: account(0x02)
+                },
+                AccountWithIndex {
+                    index: 64,
+                    account: account(0x03)
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,184 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::{chunk::BlockRecord, events::pick_events};
+use crate::{
+    save_state, AccountId, BlockNumber, ChainXEvent, ChainXEventRecord, Config,
+    XBridgeFeaturesRawEvent,
+};
+
+/// `XBridgeFeatures::BitcoinBinding` or `XBridgeFeatures::EthereumBinding`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+enum BindingEvent {
+    Bitcoin(BindingInfo),
+    Ethereum(BindingInfo),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct BindingInfo {
+    account: AccountId,
+    /// The referral channel of the account.
+    channel: Option<AccountId>,
+    /// The base58 bitcoin address, or the hex ethereum address.
+    address: String,
+    /// The account bound to the address before.
+    previous: Option<AccountId>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct AddressBinding {
+    address: String,
+    account: AccountId,
+    channel: Option<AccountId>,
+    /// The block of the latest binding.
+    height: BlockNumber,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct AddressBindings {
+    bitcoin: Vec<AddressBinding>,
+    ethereum: Vec<AddressBinding>,
+}
+
+/// Export the final bindings of the bitcoin and ethereum addresses at the target height into
+/// `address-bindings.json`.
+pub async fn export(conf: &Config) -> Result<()> {
+    let height = conf.height;
+
+    let events = pick_events(conf, binding_event).await?;
+    let bindings = replay(events);
+    log::info!(
+        "Bitcoin Address Binding Number: {}, Ethereum Address Binding Number: {}",
+        bindings.bitcoin.len(),
+        bindings.ethereum.len()
+    );
+
+    save_state(height, "address-bindings.json", &bindings)?;
+
+    Ok(())
+}
+
+fn binding_event(record: &ChainXEventRecord) -> Option<BindingEvent> {
+    match &record.event {
+        ChainXEvent::XBridgeFeatures(XBridgeFeaturesRawEvent::BitcoinBinding(
+            account,
+            channel,
+            address,
+            previous,
+        )) => Some(BindingEvent::Bitcoin(BindingInfo {
+            account: *account,
+            channel: *channel,
+            address: address.to_string(),
+            previous: *previous,
+        })),
+        ChainXEvent::XBridgeFeatures(XBridgeFeaturesRawEvent::EthereumBinding(
+            account,
+            channel,
+            address,
+            previous,
+        )) => Some(BindingEvent::Ethereum(BindingInfo {
+            account: *account,
+            channel: *channel,
+            address: format!("0x{}", hex::encode(address)),
+            previous: *previous,
+        })),
+        _ => None,
+    }
+}
+
+/// Replay the bindings in order, an address is bound to the account of its latest binding.
+fn replay(events: Vec<BlockRecord<BindingEvent>>) -> AddressBindings {
+    let mut bitcoin = BTreeMap::new();
+    let mut ethereum = BTreeMap::new();
+    for BlockRecord { height, record } in events {
+        let (bindings, info) = match record {
+            BindingEvent::Bitcoin(info) => (&mut bitcoin, info),
+            BindingEvent::Ethereum(info) => (&mut ethereum, info),
+        };
+        bindings.insert(
+            info.address.clone(),
+            AddressBinding {
+                address: info.address,
+                account: info.account,
+                channel: info.channel,
+                height,
+            },
+        );
+    }
+    AddressBindings {
+        bitcoin: bitcoin.into_iter().map(|(_, binding)| binding).collect(),
+        ethereum: ethereum.into_iter().map(|(_, binding)| binding).collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{chunk::block_records, events::fixture_events};
+
+    fn info(account: u8, address: &str, previous: Option<u8>) -> BindingInfo {
+        BindingInfo {
+            account: AccountId::repeat_byte(account),
+            channel: None,
+            address: address.to_string(),
+            previous: previous.map(AccountId::repeat_byte),
+        }
+    }
+
+    #[test]
+    fn test_replay() {
+        let events = vec![
+            (1, BindingEvent::Bitcoin(info(1, "1A", None))),
+            (2, BindingEvent::Ethereum(info(1, "0x01", None))),
+            (3, BindingEvent::Bitcoin(info(2, "1B", None))),
+            (4, BindingEvent::Bitcoin(info(3, "1A", Some(1)))),
+        ];
+        let bindings = replay(block_records(events));
+        assert_eq!(
+            bindings.bitcoin,
+            vec![
+                AddressBinding {
+                    address: "1A".to_string(),
+                    account: AccountId::repeat_byte(3),
+                    channel: None,
+                    height: 4,
+                },
+                AddressBinding {
+                    address: "1B".to_string(),
+                    account: AccountId::repeat_byte(2),
+                    channel: None,
+                    height: 3,
+                },
+            ]
+        );
+        assert_eq!(bindings.ethereum.len(), 1);
+        assert_eq!(bindings.ethereum[0].height, 2);
+    }
+
+    #[test]
+    fn test_fixture() {
+        let bindings = replay(fixture_events("address_bindings", binding_event));
+        assert_eq!(
+            bindings.bitcoin,
+            vec![AddressBinding {
+                address: "12ZEw5Hcv1hTb6YUQJ69y1V7uhcoDz92PH".to_string(),
+                account: AccountId::repeat_byte(3),
+                channel: None,
+                height: 200,
+            }]
+        );
+        assert_eq!(
+            bindings.ethereum,
+            vec![AddressBinding {
+                address: format!("0x{}", hex::encode([0x22; 20])),
+                account: AccountId::repeat_byte(2),
+                channel: None,
+                height: 200,
+            }]
+        );
+    }
+}
@@ -1,15 +1,19 @@
 // Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
 
-use std::cmp::Ordering;
+use std::{cmp::Ordering, collections::BTreeMap};
 
 use anyhow::Result;
-use chainx_state_exporter::*;
 use serde::{Deserialize, Serialize};
 
+use crate::{
+    load_state, save_state, AccountId, AssetInfo, AssetType, Balance, ChainX, Config,
+    TotalAssetInfo,
+};
+
 #[derive(Debug, Serialize, Deserialize)]
-struct AccountWithAssets {
-    account: AccountId,
-    assets: Vec<AssetInfo>,
+pub(super) struct AccountWithAssets {
+    pub account: AccountId,
+    pub assets: Vec<AssetInfo>,
 }
 
 impl PartialEq for AccountWithAssets {
@@ -32,18 +36,14 @@ impl Ord for AccountWithAssets {
     }
 }
 
-#[async_std::main]
-async fn main() -> Result<()> {
-    env_logger::init();
-
-    let conf = CmdConfig::init()?;
+pub async fn export(conf: &Config) -> Result<()> {
     let height = conf.height;
 
     let accounts: Vec<AccountId> = load_state(height, "accounts.json")?;
     let account_number = accounts.len();
     log::info!("Total Account Number: {}", account_number);
 
-    let chainx = ChainX::new(&conf.chainx_ws_url).await?;
+    let chainx = ChainX::connect(conf).await?;
     let hash = chainx.block_hash(Some(height)).await?;
     log::info!("Block Height {}, Hash: {:?}", height, hash);
 
@@ -56,7 +56,7 @@ async fn main() -> Result<()> {
     const CONNECTION_NUM: usize = 40;
     let mut handles = vec![];
     for (id, accounts) in accounts.chunks(account_number / CONNECTION_NUM).enumerate() {
-        let chainx = ChainX::new(&conf.chainx_ws_url).await?;
+        let chainx = ChainX::connect(conf).await?;
         let accounts = accounts.iter().cloned().collect::<Vec<_>>();
         let handle =
             async_std::task::spawn(
@@ -91,3 +91,49 @@ async fn main() -> Result<()> {
 
     Ok(())
 }
+
+pub fn verify(conf: &Config) -> Result<()> {
+    let height = conf.height;
+
+    let total_assets: [TotalAssetInfo; 4] = load_state(height, "assets-total.json")?;
+    let (total_pcx, total_xbtc, total_lbtc, total_sdot) = (
+        total_assets[0].details.clone(),
+        total_assets[1].details.clone(),
+        total_assets[2].details.clone(),
+        total_assets[3].details.clone(),
+    );
+
+    let assets_accounts: Vec<AccountWithAssets> = load_state(height, "assets.json")?;
+
+    let mut total_account_pcx = BTreeMap::new();
+    let mut total_account_xbtc = BTreeMap::new();
+    let mut total_account_lbtc = BTreeMap::new();
+    let mut total_account_sdot = BTreeMap::new();
+    for account in assets_accounts {
+        let assets = account.assets;
+        for asset in assets {
+            match asset.name.as_str() {
+                "PCX" => sum_asset(&mut total_account_pcx, asset.details),
+                "BTC" => sum_asset(&mut total_account_xbtc, asset.details),
+                "L-BTC" => sum_asset(&mut total_account_lbtc, asset.details),
+                "SDOT" => sum_asset(&mut total_account_sdot, asset.details),
+                _ => {}
+            }
+        }
+    }
+
+    assert_eq!(total_pcx, total_account_pcx);
+    assert_eq!(total_xbtc, total_account_xbtc);
+    assert_eq!(total_lbtc, total_account_lbtc);
+    assert_eq!(total_sdot, total_account_sdot);
+
+    Ok(())
+}
+
+fn sum_asset(sum: &mut BTreeMap<AssetType, Balance>, add: BTreeMap<AssetType, Balance>) {
+    for (asset, balance) in add {
+        sum.entry(asset)
+            .and_modify(|value| *value += balance)
+            .or_insert(balance);
+    }
+}
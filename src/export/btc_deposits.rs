@@ -0,0 +1,379 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+use std::collections::BTreeMap;
+
+use anyhow::{ensure, Result};
+use serde::{Deserialize, Serialize};
+
+use super::assets::AccountWithAssets;
+use super::withdrawals::Withdrawal;
+use super::{chunk::BlockRecord, events::pick_events};
+use crate::{
+    load_state, save_state, AccountId, ApplicationState, Balance, BlockNumber, Chain, ChainXEvent,
+    ChainXEventRecord, Config, TxState, XBitcoinRawEvent,
+};
+
+const OUTPUT: &str = "btc-deposits.json";
+
+/// The per-account proof of `verify btc-deposits`.
+const RECONCILIATION: &str = "btc-deposits-reconciliation.json";
+
+/// A deposit of `XBitcoin::Deposit`, or `XBitcoin::DepositPending` if the bitcoin address has
+/// not been bound to an account.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct Deposit {
+    account: AccountId,
+    chain: Chain,
+    token: String,
+    balance: Balance,
+    memo: String,
+    addr: String,
+    /// `None` for the pending deposits.
+    tx_hash: Option<String>,
+    tx_state: Option<TxState>,
+    pending: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct DepositEntry {
+    height: BlockNumber,
+    #[serde(flatten)]
+    deposit: Deposit,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct AccountDeposits {
+    account: AccountId,
+    /// The sum of the X-BTC deposits.
+    deposited: Balance,
+    /// The sum of the pending deposits.
+    pending: Balance,
+    entries: Vec<DepositEntry>,
+}
+
+/// Export the X-BTC deposit ledger of all accounts up to the target height into
+/// `btc-deposits.json`.
+pub async fn export(conf: &Config) -> Result<()> {
+    let height = conf.height;
+
+    let deposits = pick_events(conf, deposit).await?;
+    let ledger = ledger(deposits);
+    log::info!(
+        "Deposit Account Number: {}, Deposit Number: {}",
+        ledger.len(),
+        ledger
+            .iter()
+            .map(|account| account.entries.len())
+            .sum::<usize>()
+    );
+
+    save_state(height, OUTPUT, &ledger)?;
+
+    Ok(())
+}
+
+/// The X-BTC of an account by the deposits and the withdrawals, and by `assets.json`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct Reconciliation {
+    deposited: Balance,
+    /// The sum of the finished withdrawals, the balances of the pending ones are still reserved
+    /// in `assets.json`.
+    withdrawn: Balance,
+    balance: Balance,
+}
+
+impl Reconciliation {
+    /// The X-BTC received from (or sent to, if negative) the other accounts.
+    fn transferred(&self) -> i128 {
+        self.balance as i128 - (self.deposited as i128 - self.withdrawn as i128)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct AccountReconciliation {
+    account: AccountId,
+    #[serde(flatten)]
+    reconciliation: Reconciliation,
+    /// The balance minus the deposits and plus the withdrawals, which is the X-BTC transferred.
+    difference: i128,
+}
+
+/// Reconcile the deposit ledger minus the withdrawals of `withdrawals.json` against the `BTC`
+/// of `assets.json`.
+///
+/// X-BTC can only be issued by the deposits and destroyed by the withdrawals, so the total
+/// balance must be exactly the total deposits minus the total withdrawals. The balance of an
+/// account may differ from its own deposits minus withdrawals since X-BTC can be transferred
+/// and traded, these accounts are only reported.
+///
+/// The reconciliation of every account is written into `btc-deposits-reconciliation.json`.
+pub fn verify(conf: &Config) -> Result<()> {
+    let height = conf.height;
+
+    let ledger: Vec<AccountDeposits> = load_state(height, OUTPUT)?;
+    let withdrawals: Vec<Withdrawal> = load_state(height, "withdrawals.json")?;
+    let assets_accounts: Vec<AccountWithAssets> = load_state(height, "assets.json")?;
+    let accounts = reconcile(&ledger, &withdrawals, &assets_accounts);
+
+    let mut total = Reconciliation::default();
+    let mut transferred_number = 0;
+    for (account, reconciliation) in &accounts {
+        total.deposited += reconciliation.deposited;
+        total.withdrawn += reconciliation.withdrawn;
+        total.balance += reconciliation.balance;
+        if reconciliation.transferred() != 0 {
+            transferred_number += 1;
+            log::warn!(
+                "Account {:?}, X-BTC Balance {} != Deposited {} - Withdrawn {}",
+                account,
+                reconciliation.balance,
+                reconciliation.deposited,
+                reconciliation.withdrawn
+            );
+        }
+    }
+    let reconciliations = accounts
+        .into_iter()
+        .map(|(account, reconciliation)| AccountReconciliation {
+            account,
+            difference: reconciliation.transferred(),
+            reconciliation,
+        })
+        .collect::<Vec<_>>();
+    save_state(height, RECONCILIATION, &reconciliations)?;
+
+    log::info!(
+        "Total X-BTC Balance: {}, Total Deposited: {}, Total Withdrawn: {}, \
+         Accounts With Transferred X-BTC: {}",
+        total.balance,
+        total.deposited,
+        total.withdrawn,
+        transferred_number
+    );
+    ensure!(
+        total.transferred() == 0,
+        "The total X-BTC balance {} mismatches the deposits {} minus the withdrawals {}",
+        total.balance,
+        total.deposited,
+        total.withdrawn
+    );
+
+    Ok(())
+}
+
+/// The `BTC` deposits, finished withdrawals and balance of each account.
+fn reconcile(
+    ledger: &[AccountDeposits],
+    withdrawals: &[Withdrawal],
+    assets_accounts: &[AccountWithAssets],
+) -> BTreeMap<AccountId, Reconciliation> {
+    let mut accounts = BTreeMap::<AccountId, Reconciliation>::new();
+    for account in ledger {
+        accounts.entry(account.account).or_default().deposited += account.deposited;
+    }
+    for withdrawal in withdrawals {
+        let finished = matches!(
+            withdrawal.state,
+            ApplicationState::NormalFinish | ApplicationState::RootFinish
+        );
+        if withdrawal.token == "BTC" && finished {
+            accounts.entry(withdrawal.account).or_default().withdrawn += withdrawal.balance;
+        }
+    }
+    for account in assets_accounts {
+        let balance = account
+            .assets
+            .iter()
+            .filter(|asset| asset.name == "BTC")
+            .flat_map(|asset| asset.details.values())
+            .sum::<Balance>();
+        if balance != 0 {
+            accounts.entry(account.account).or_default().balance += balance;
+        }
+    }
+    accounts
+}
+
+fn deposit(record: &ChainXEventRecord) -> Option<Deposit> {
+    let text = |bytes: &[u8]| String::from_utf8_lossy(bytes).into_owned();
+    match &record.event {
+        ChainXEvent::XBitcoin(XBitcoinRawEvent::Deposit(
+            account,
+            chain,
+            token,
+            balance,
+            memo,
+            addr,
+            tx_hash,
+            tx_state,
+        )) => Some(Deposit {
+            account: *account,
+            chain: chain.clone(),
+            token: text(token),
+            balance: *balance,
+            memo: text(memo),
+            addr: text(addr),
+            tx_hash: Some(format!("0x{}", hex::encode(tx_hash))),
+            tx_state: Some(*tx_state),
+            pending: false,
+        }),
+        ChainXEvent::XBitcoin(XBitcoinRawEvent::DepositPending(
+            account,
+            chain,
+            token,
+            balance,
+            addr,
+        )) => Some(Deposit {
+            account: *account,
+            chain: chain.clone(),
+            token: text(token),
+            balance: *balance,
+            memo: String::new(),
+            addr: text(addr),
+            tx_hash: None,
+            tx_state: None,
+            pending: true,
+        }),
+        _ => None,
+    }
+}
+
+/// Group the deposits by the accounts, only the `BTC` deposits are summed.
+fn ledger(deposits: Vec<BlockRecord<Deposit>>) -> Vec<AccountDeposits> {
+    let mut ledger = BTreeMap::<AccountId, AccountDeposits>::new();
+    for BlockRecord { height, record } in deposits {
+        let account = ledger
+            .entry(record.account)
+            .or_insert_with(|| AccountDeposits {
+                account: record.account,
+                ..Default::default()
+            });
+        if record.token == "BTC" {
+            if record.pending {
+                account.pending += record.balance;
+            } else {
+                account.deposited += record.balance;
+            }
+        }
+        account.entries.push(DepositEntry {
+            height,
+            deposit: record,
+        });
+    }
+    ledger.into_iter().map(|(_, account)| account).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AssetInfo, AssetType};
+
+    fn entry(
+        height: BlockNumber,
+        byte: u8,
+        balance: Balance,
+        pending: bool,
+    ) -> BlockRecord<Deposit> {
+        let (tx_hash, tx_state) = if pending {
+            (None, None)
+        } else {
+            (Some("0x01".to_string()), Some(TxState::Confirmed))
+        };
+        BlockRecord {
+            height,
+            record: Deposit {
+                account: AccountId::repeat_byte(byte),
+                chain: Chain::Bitcoin,
+                token: "BTC".to_string(),
+                balance,
+                memo: String::new(),
+                addr: "1BoatSLRHtKNngkdXEeobR76b53LETtpyT".to_string(),
+                tx_hash,
+                tx_state,
+                pending,
+            },
+        }
+    }
+
+    #[test]
+    fn test_ledger() {
+        let ledger = ledger(vec![
+            entry(1, 2, 100, false),
+            entry(2, 1, 50, true),
+            entry(3, 2, 30, false),
+            entry(4, 1, 70, false),
+        ]);
+        assert_eq!(ledger.len(), 2);
+        assert_eq!(ledger[0].account, AccountId::repeat_byte(1));
+        assert_eq!((ledger[0].deposited, ledger[0].pending), (70, 50));
+        assert_eq!(ledger[0].entries[0].height, 2);
+        assert_eq!(ledger[1].deposited, 130);
+        assert_eq!(ledger[1].entries.len(), 2);
+    }
+
+    #[test]
+    fn test_reconcile() {
+        let ledger = ledger(vec![entry(1, 1, 100, false), entry(2, 2, 50, false)]);
+        let withdrawal = |byte: u8, balance, state| -> Withdrawal {
+            serde_json::from_value(serde_json::json!({
+                "id": byte,
+                "height": 3,
+                "account": AccountId::repeat_byte(byte),
+                "chain": Chain::Bitcoin,
+                "token": "BTC",
+                "balance": balance,
+                "memo": "",
+                "addr": "1BoatSLRHtKNngkdXEeobR76b53LETtpyT",
+                "state": state,
+                "finish_height": null,
+                "tx_hash": null,
+                "tx_state": null,
+                "pending": false,
+            }))
+            .unwrap()
+        };
+        let withdrawals = vec![
+            withdrawal(1, 30, ApplicationState::NormalFinish),
+            withdrawal(1, 10, ApplicationState::Applying),
+            withdrawal(2, 20, ApplicationState::NormalCancel),
+        ];
+        let assets_accounts = vec![AccountWithAssets {
+            account: AccountId::repeat_byte(2),
+            assets: vec![AssetInfo {
+                name: "BTC".to_string(),
+                details: vec![(AssetType::Free, 100), (AssetType::ReservedWithdrawal, 20)]
+                    .into_iter()
+                    .collect(),
+            }],
+        }];
+
+        let accounts = reconcile(&ledger, &withdrawals, &assets_accounts);
+        let a = &accounts[&AccountId::repeat_byte(1)];
+        assert_eq!((a.deposited, a.withdrawn, a.balance), (100, 30, 0));
+        assert_eq!(a.transferred(), -70);
+        let b = &accounts[&AccountId::repeat_byte(2)];
+        assert_eq!((b.deposited, b.withdrawn, b.balance), (50, 0, 120));
+        assert_eq!(b.transferred(), 70);
+
+        let proof = serde_json::to_value(AccountReconciliation {
+            account: AccountId::repeat_byte(2),
+            reconciliation: b.clone(),
+            difference: b.transferred(),
+        })
+        .unwrap();
+        assert_eq!(
+            (
+                &proof["deposited"],
+                &proof["withdrawn"],
+                &proof["balance"],
+                &proof["difference"]
+            ),
+            (
+                &serde_json::json!(50),
+                &serde_json::json!(0),
+                &serde_json::json!(120),
+                &serde_json::json!(70)
+            )
+        );
+    }
+}
@@ -0,0 +1,144 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::{chunk::BlockRecord, events::pick_events};
+use crate::{
+    save_state, AccountId, BlockNumber, ChainXEvent, ChainXEventRecord, Config,
+    XBridgeCommonRawEvent,
+};
+
+/// `XBridgeCommon::ChannelBinding`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct ChannelBindingEvent {
+    token: String,
+    account: AccountId,
+    /// The validator of the referral channel.
+    channel: AccountId,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct ChannelBinding {
+    account: AccountId,
+    channel: AccountId,
+    /// The block of the latest binding.
+    height: BlockNumber,
+}
+
+/// Export the referral channel of every account for each token at the target height into
+/// `channel-bindings.json`.
+pub async fn export(conf: &Config) -> Result<()> {
+    let height = conf.height;
+
+    let events = pick_events(conf, channel_binding_event).await?;
+    let bindings = replay(events);
+    for (token, bindings) in &bindings {
+        log::info!(
+            "Token {}, Channel Binding Number: {}",
+            token,
+            bindings.len()
+        );
+    }
+
+    save_state(height, "channel-bindings.json", &bindings)?;
+
+    Ok(())
+}
+
+fn channel_binding_event(record: &ChainXEventRecord) -> Option<ChannelBindingEvent> {
+    match &record.event {
+        ChainXEvent::XBridgeCommon(XBridgeCommonRawEvent::ChannelBinding(
+            token,
+            account,
+            channel,
+        )) => Some(ChannelBindingEvent {
+            token: String::from_utf8_lossy(token).into_owned(),
+            account: *account,
+            channel: *channel,
+        }),
+        _ => None,
+    }
+}
+
+/// Replay the bindings in order, the latest binding of an account for a token takes effect.
+fn replay(events: Vec<BlockRecord<ChannelBindingEvent>>) -> BTreeMap<String, Vec<ChannelBinding>> {
+    let mut bindings = BTreeMap::<String, BTreeMap<AccountId, ChannelBinding>>::new();
+    for BlockRecord { height, record } in events {
+        bindings.entry(record.token).or_default().insert(
+            record.account,
+            ChannelBinding {
+                account: record.account,
+                channel: record.channel,
+                height,
+            },
+        );
+    }
+    bindings
+        .into_iter()
+        .map(|(token, bindings)| (token, bindings.into_iter().map(|(_, b)| b).collect()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{chunk::block_records, events::fixture_events};
+
+    #[test]
+    fn test_replay() {
+        let event = |token: &str, account, channel| ChannelBindingEvent {
+            token: token.to_string(),
+            account: AccountId::repeat_byte(account),
+            channel: AccountId::repeat_byte(channel),
+        };
+        let events = vec![
+            (1, event("BTC", 1, 0xa)),
+            (2, event("SDOT", 1, 0xb)),
+            (3, event("BTC", 2, 0xa)),
+            (4, event("BTC", 1, 0xc)),
+        ];
+        let bindings = replay(block_records(events));
+        assert_eq!(bindings.len(), 2);
+        assert_eq!(
+            bindings["BTC"],
+            vec![
+                ChannelBinding {
+                    account: AccountId::repeat_byte(1),
+                    channel: AccountId::repeat_byte(0xc),
+                    height: 4,
+                },
+                ChannelBinding {
+                    account: AccountId::repeat_byte(2),
+                    channel: AccountId::repeat_byte(0xa),
+                    height: 3,
+                },
+            ]
+        );
+        assert_eq!(bindings["SDOT"][0].channel, AccountId::repeat_byte(0xb));
+    }
+
+    #[test]
+    fn test_fixture() {
+        let bindings = replay(fixture_events("channel_bindings", channel_binding_event));
+        assert_eq!(bindings.len(), 2);
+        assert_eq!(
+            bindings["BTC"],
+            vec![ChannelBinding {
+                account: AccountId::repeat_byte(1),
+                channel: AccountId::repeat_byte(0xb),
+                height: 600,
+            }]
+        );
+        assert_eq!(
+            bindings["SDOT"],
+            vec![ChannelBinding {
+                account: AccountId::repeat_byte(2),
+                channel: AccountId::repeat_byte(0xa),
+                height: 600,
+            }]
+        );
+    }
+}
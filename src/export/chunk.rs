@@ -0,0 +1,175 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+use std::future::Future;
+
+use anyhow::Result;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{archive_exists, load_archive, save_archive, BlockNumber, ChainX, Config};
+
+pub(crate) const CHUNK_NUMBER: u64 = 10_000;
+
+/// The block ranges of the chunks between `from` and `to` (both inclusive, `from` > 0),
+/// the chunks are aligned to `CHUNK_NUMBER` from block #1 and clamped to the given range.
+pub(crate) fn chunks(
+    from: BlockNumber,
+    to: BlockNumber,
+) -> impl Iterator<Item = (BlockNumber, BlockNumber)> {
+    (aligned_begin(from)..=to)
+        .step_by(CHUNK_NUMBER as usize)
+        .map(move |begin| (begin.max(from), (begin + CHUNK_NUMBER - 1).min(to)))
+}
+
+/// The beginning of the full chunk which contains the height.
+pub(crate) fn aligned_begin(height: BlockNumber) -> BlockNumber {
+    (height - 1) / CHUNK_NUMBER * CHUNK_NUMBER + 1
+}
+
+pub(crate) fn chunk_filename(begin: BlockNumber, end: BlockNumber) -> String {
+    format!("{}-{}.json", begin, end)
+}
+
+/// The block range of a chunk file, `None` if it's not a chunk file.
+pub(crate) fn parse_chunk_filename(filename: &str) -> Option<(BlockNumber, BlockNumber)> {
+    let (begin, end) = filename.strip_suffix(".json")?.split_once('-')?;
+    Some((begin.parse().ok()?, end.parse().ok()?))
+}
+
+pub(crate) fn full_chunk_filename(height: BlockNumber) -> String {
+    let begin = aligned_begin(height);
+    chunk_filename(begin, begin + CHUNK_NUMBER - 1)
+}
+
+/// Scan all blocks up to the target height by chunks and save the records of each chunk into
+/// `[archive]/[begin]-[end].json`, the existing chunks are skipped.
+///
+/// The blocks of a chunk are scanned by 50 connections concurrently, `scan` takes a connection,
+/// its id and the heights it handles.
+pub(crate) async fn export_archive<T, F, Fut>(conf: &Config, archive: &str, scan: F) -> Result<()>
+where
+    T: Serialize + Send + 'static,
+    F: Fn(ChainX, usize, Vec<BlockNumber>) -> Fut,
+    Fut: Future<Output = Result<Vec<T>>> + Send + 'static,
+{
+    for (begin, end) in chunks(1, conf.height) {
+        if archive_exists(archive, chunk_filename(begin, end))?
+            || archive_exists(archive, full_chunk_filename(begin))?
+        {
+            log::info!("[{}] {}-{} already got", archive, begin, end);
+            continue;
+        }
+
+        // Each connection handles 1/50 of the total blocks, and 50 connections are required
+        const CONNECTION_NUM: u64 = 50;
+        let chunk_size = CHUNK_NUMBER / CONNECTION_NUM;
+
+        let heights = (begin..=end).collect::<Vec<_>>();
+
+        let mut handles = vec![];
+        for (id, chunk_heights) in heights.chunks(chunk_size as usize).enumerate() {
+            let chainx = ChainX::connect(conf).await?;
+            let handle = async_std::task::spawn(scan(chainx, id, chunk_heights.to_vec()));
+            handles.push((id, handle));
+        }
+
+        let mut total_records = vec![];
+        for (id, handle) in handles {
+            let records = handle.await?;
+            log::info!(
+                "[{}] [{}] Connection Finished, Record Number: {}",
+                archive,
+                id,
+                records.len()
+            );
+            total_records.extend(records);
+        }
+
+        save_archive(archive, chunk_filename(begin, end), &total_records)?;
+        log::info!(
+            "[{}] Record Number of Block #{}-#{}: {}",
+            archive,
+            begin,
+            end,
+            total_records.len()
+        );
+    }
+    Ok(())
+}
+
+/// A record of an archive and the block it's found in.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct BlockRecord<T> {
+    pub height: BlockNumber,
+    pub record: T,
+}
+
+/// The records of the events at the heights, for the tests of the event histories.
+#[cfg(test)]
+pub(crate) fn block_records<T>(events: Vec<(BlockNumber, T)>) -> Vec<BlockRecord<T>> {
+    events
+        .into_iter()
+        .map(|(height, record)| BlockRecord { height, record })
+        .collect()
+}
+
+/// Load the records of the blocks between `from` and `to` (both inclusive) from the chunks,
+/// a clamped chunk can also be taken from the full chunk containing it.
+pub(crate) fn load_archive_records<T: DeserializeOwned>(
+    archive: &str,
+    from: BlockNumber,
+    to: BlockNumber,
+) -> Result<Vec<BlockRecord<T>>> {
+    let mut records = vec![];
+    for (begin, end) in chunks(from, to) {
+        if archive_exists(archive, chunk_filename(begin, end))? {
+            records.extend(load_archive::<_, Vec<BlockRecord<T>>>(
+                archive,
+                chunk_filename(begin, end),
+            )?);
+        } else {
+            let chunk: Vec<BlockRecord<T>> = load_archive(archive, full_chunk_filename(begin))?;
+            records.extend(
+                chunk
+                    .into_iter()
+                    .filter(|record| begin <= record.height && record.height <= end),
+            );
+        }
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunks() {
+        assert_eq!(chunks(1, 0).count(), 0);
+        assert_eq!(chunks(1, 5).collect::<Vec<_>>(), vec![(1, 5)]);
+        assert_eq!(chunks(1, 10_000).collect::<Vec<_>>(), vec![(1, 10_000)]);
+        assert_eq!(
+            chunks(1, 23_456).collect::<Vec<_>>(),
+            vec![(1, 10_000), (10_001, 20_000), (20_001, 23_456)]
+        );
+        assert_eq!(
+            chunks(23_457, 40_000).collect::<Vec<_>>(),
+            vec![(23_457, 30_000), (30_001, 40_000)]
+        );
+        assert_eq!(
+            chunks(23_457, 23_460).collect::<Vec<_>>(),
+            vec![(23_457, 23_460)]
+        );
+    }
+
+    #[test]
+    fn test_parse_chunk_filename() {
+        assert_eq!(parse_chunk_filename("1-10000.json"), Some((1, 10_000)));
+        assert_eq!(
+            parse_chunk_filename(&chunk_filename(20_001, 23_456)),
+            Some((20_001, 23_456))
+        );
+        assert_eq!(parse_chunk_filename("genesis.json"), None);
+        assert_eq!(parse_chunk_filename("index.json"), None);
+        assert_eq!(parse_chunk_filename("missing.log"), None);
+    }
+}
@@ -3,9 +3,10 @@
 use std::cmp::Ordering;
 
 use anyhow::Result;
-use chainx_state_exporter::*;
 use serde::{Deserialize, Serialize};
 
+use crate::{load_state, save_state, AccountId, Balance, ChainX, Config, TotalDepositWeightInfoV1};
+
 #[derive(Debug, Serialize, Deserialize)]
 struct AccountWithDepositWeightInfo {
     account: AccountId,
@@ -33,18 +34,14 @@ impl Ord for AccountWithDepositWeightInfo {
     }
 }
 
-#[async_std::main]
-async fn main() -> Result<()> {
-    env_logger::init();
-
-    let conf = CmdConfig::init()?;
+pub async fn export(conf: &Config) -> Result<()> {
     let height = conf.height;
 
     let accounts: Vec<AccountId> = load_state(height, "accounts.json")?;
     let account_number = accounts.len();
     log::info!("Total Account Number: {}", account_number);
 
-    let chainx = ChainX::new(&conf.chainx_ws_url).await?;
+    let chainx = ChainX::connect(conf).await?;
     let hash = chainx.block_hash(Some(height)).await?;
     log::info!("Block Height {}, Hash: {:?}", height, hash);
 
@@ -72,7 +69,7 @@ async fn main() -> Result<()> {
     const CONNECTION_NUM: usize = 40;
     let mut handles = vec![];
     for (id, accounts) in accounts.chunks(account_number / CONNECTION_NUM).enumerate() {
-        let chainx = ChainX::new(&conf.chainx_ws_url).await?;
+        let chainx = ChainX::connect(conf).await?;
         let accounts = accounts.iter().cloned().collect::<Vec<_>>();
         let handle = async_std::task::spawn(async move {
             chainx
@@ -0,0 +1,403 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use anyhow::{anyhow, ensure, Result};
+use serde::{Deserialize, Serialize};
+
+use super::assets::AccountWithAssets;
+use super::{chunk::BlockRecord, events::pick_events};
+use crate::{
+    load_state, save_state, AccountId, AssetType, Balance, BlockNumber, ChainX, ChainXEvent,
+    ChainXEventRecord, Config, OrderIndex, OrderStatus, OrderType, Price, Side, TotalAssetInfo,
+    TradingPair, TradingPairIndex, XSpotRawEvent,
+};
+const OUTPUT: &str = "dex-orders.json";
+
+/// The events of the orders, every `FillOrder` is followed by the `UpdateOrder`s of both
+/// orders, so only these two are needed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+enum OrderEvent {
+    /// `XSpot::PutOrder`
+    Put {
+        account: AccountId,
+        index: OrderIndex,
+        pair: TradingPairIndex,
+        order_type: OrderType,
+        price: Price,
+        side: Side,
+        amount: Balance,
+        created_at: BlockNumber,
+    },
+    /// `XSpot::UpdateOrder`
+    Update {
+        account: AccountId,
+        index: OrderIndex,
+        already_filled: Balance,
+        status: OrderStatus,
+        remaining: Balance,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct Order {
+    account: AccountId,
+    index: OrderIndex,
+    pair: TradingPairIndex,
+    order_type: OrderType,
+    side: Side,
+    price: Price,
+    /// The amount of the base token.
+    amount: Balance,
+    created_at: BlockNumber,
+    already_filled: Balance,
+    status: OrderStatus,
+    /// The remaining reserved balance of the latest `UpdateOrder`, `None` if never updated.
+    remaining: Option<Balance>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct OpenOrder {
+    #[serde(flatten)]
+    order: Order,
+    /// The quote token for the buy orders and the base token for the sell orders.
+    reserved_token: String,
+    reserved: Balance,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct AccountOrders {
+    account: AccountId,
+    /// Sorted by the trading pair and the order index.
+    orders: Vec<OpenOrder>,
+    /// The reserved balances of the orders by the tokens.
+    reserved: BTreeMap<String, Balance>,
+}
+
+/// Export the open orders of all accounts at the target height into `dex-orders.json`.
+pub async fn export(conf: &Config) -> Result<()> {
+    let height = conf.height;
+
+    let events = pick_events(conf, order_event).await?;
+    let orders = rebuild(events);
+
+    let chainx = ChainX::connect(conf).await?;
+    let hash = chainx.block_hash(Some(height)).await?;
+    let pairs = chainx
+        .trading_pairs(hash)
+        .await?
+        .ok_or_else(|| anyhow!("No trading pair at Block #{}", height))?
+        .into_iter()
+        .map(|pair| (pair.id, pair))
+        .collect::<BTreeMap<_, _>>();
+    let precisions = load_state::<_, Vec<TotalAssetInfo>>(height, "assets-total.json")?
+        .into_iter()
+        .map(|asset| (asset.name, asset.precision))
+        .collect::<BTreeMap<_, _>>();
+
+    let mut accounts = BTreeMap::<AccountId, AccountOrders>::new();
+    for order in orders {
+        let pair = pairs
+            .get(&order.pair)
+            .ok_or_else(|| anyhow!("Unknown trading pair #{}", order.pair))?;
+        let precision = *precisions
+            .get(&pair.assets)
+            .ok_or_else(|| anyhow!("Unknown token {}", pair.assets))?;
+        let (reserved_token, reserved) = reserved(&order, pair, precision);
+        let account = accounts
+            .entry(order.account)
+            .or_insert_with(|| AccountOrders {
+                account: order.account,
+                ..Default::default()
+            });
+        *account.reserved.entry(reserved_token.clone()).or_default() += reserved;
+        account.orders.push(OpenOrder {
+            order,
+            reserved_token,
+            reserved,
+        });
+    }
+    let accounts = accounts
+        .into_iter()
+        .map(|(_, mut account)| {
+            account
+                .orders
+                .sort_unstable_by_key(|order| (order.order.pair, order.order.index));
+            account
+        })
+        .collect::<Vec<_>>();
+    log::info!(
+        "Open Order Account Number: {}, Open Order Number: {}",
+        accounts.len(),
+        accounts
+            .iter()
+            .map(|account| account.orders.len())
+            .sum::<usize>()
+    );
+
+    save_state(height, OUTPUT, &accounts)?;
+
+    Ok(())
+}
+
+/// Verify the reserved balances of the open orders against the `ReservedDexSpot` of
+/// `assets.json`, they should be exactly the same for every account and token.
+pub fn verify(conf: &Config) -> Result<()> {
+    let height = conf.height;
+
+    let accounts: Vec<AccountOrders> = load_state(height, OUTPUT)?;
+    let mut orders_reserved = BTreeMap::new();
+    for account in accounts {
+        for (token, reserved) in account.reserved {
+            orders_reserved.insert((account.account, token), reserved);
+        }
+    }
+
+    let assets_accounts: Vec<AccountWithAssets> = load_state(height, "assets.json")?;
+    let mut assets_reserved = BTreeMap::new();
+    for account in assets_accounts {
+        for asset in account.assets {
+            match asset.details.get(&AssetType::ReservedDexSpot) {
+                Some(reserved) if *reserved > 0 => {
+                    assets_reserved.insert((account.account, asset.name), *reserved);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    let keys = orders_reserved
+        .keys()
+        .chain(assets_reserved.keys())
+        .collect::<BTreeSet<_>>();
+    let mut mismatch_number = 0;
+    for key in keys {
+        let orders = orders_reserved.get(key).copied().unwrap_or_default();
+        let assets = assets_reserved.get(key).copied().unwrap_or_default();
+        if orders != assets {
+            mismatch_number += 1;
+            log::error!(
+                "Account {:?}, Token {}: Orders Reserved {} != ReservedDexSpot {}",
+                key.0,
+                key.1,
+                orders,
+                assets
+            );
+        }
+    }
+    log::info!(
+        "Open Order Reserved Number: {}, ReservedDexSpot Number: {}",
+        orders_reserved.len(),
+        assets_reserved.len()
+    );
+    ensure!(
+        mismatch_number == 0,
+        "The open orders mismatch ReservedDexSpot of {} accounts and tokens",
+        mismatch_number
+    );
+
+    Ok(())
+}
+
+fn order_event(record: &ChainXEventRecord) -> Option<OrderEvent> {
+    match &record.event {
+        ChainXEvent::XSpot(XSpotRawEvent::PutOrder(
+            account,
+            index,
+            pair,
+            order_type,
+            price,
+            side,
+            amount,
+            created_at,
+        )) => Some(OrderEvent::Put {
+            account: *account,
+            index: *index,
+            pair: *pair,
+            order_type: *order_type,
+            price: *price,
+            side: *side,
+            amount: *amount,
+            created_at: *created_at,
+        }),
+        ChainXEvent::XSpot(XSpotRawEvent::UpdateOrder(
+            account,
+            index,
+            already_filled,
+            _,
+            status,
+            remaining,
+            _,
+        )) => Some(OrderEvent::Update {
+            account: *account,
+            index: *index,
+            already_filled: *already_filled,
+            status: *status,
+            remaining: *remaining,
+        }),
+        _ => None,
+    }
+}
+
+/// Replay the events in order, the orders which are not filled or canceled are open.
+fn rebuild(events: Vec<BlockRecord<OrderEvent>>) -> Vec<Order> {
+    let mut orders = BTreeMap::new();
+    for BlockRecord { height, record } in events {
+        match record {
+            OrderEvent::Put {
+                account,
+                index,
+                pair,
+                order_type,
+                price,
+                side,
+                amount,
+                created_at,
+            } => {
+                orders.insert(
+                    (account, index),
+                    Order {
+                        account,
+                        index,
+                        pair,
+                        order_type,
+                        side,
+                        price,
+                        amount,
+                        created_at,
+                        already_filled: 0,
+                        status: OrderStatus::ZeroFill,
+                        remaining: None,
+                    },
+                );
+            }
+            OrderEvent::Update {
+                account,
+                index,
+                already_filled,
+                status,
+                remaining,
+            } => match orders.get_mut(&(account, index)) {
+                Some(order) => {
+                    order.already_filled = already_filled;
+                    order.status = status;
+                    order.remaining = Some(remaining);
+                }
+                None => log::warn!(
+                    "Block Height {}, UpdateOrder of an unknown order {:?}#{}",
+                    height,
+                    account,
+                    index
+                ),
+            },
+        }
+    }
+    orders
+        .into_iter()
+        .map(|(_, order)| order)
+        .filter(|order| {
+            matches!(
+                order.status,
+                OrderStatus::ZeroFill | OrderStatus::ParitialFill
+            )
+        })
+        .collect()
+}
+
+/// The reserved token and balance of an open order.
+///
+/// The remaining of the latest `UpdateOrder` is the reserved balance. An order never updated
+/// reserves its amount of the base token if it's a sell order, or the amount converted to the
+/// quote token by the price (`amount * price / 10^precision of the base token`).
+fn reserved(order: &Order, pair: &TradingPair, base_precision: u16) -> (String, Balance) {
+    match order.side {
+        Side::Sell => (
+            pair.assets.clone(),
+            order
+                .remaining
+                .unwrap_or(order.amount - order.already_filled),
+        ),
+        Side::Buy => (
+            pair.currency.clone(),
+            order.remaining.unwrap_or_else(|| {
+                ((order.amount - order.already_filled) as u128 * order.price as u128
+                    / 10u128.pow(base_precision.into())) as Balance
+            }),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::chunk::block_records;
+
+    fn put(byte: u8, index: OrderIndex, side: Side) -> OrderEvent {
+        OrderEvent::Put {
+            account: AccountId::repeat_byte(byte),
+            index,
+            pair: 0,
+            order_type: OrderType::Limit,
+            price: 1_000,
+            side,
+            amount: 100_000_000,
+            created_at: 1,
+        }
+    }
+
+    fn update(byte: u8, index: OrderIndex, status: OrderStatus, remaining: Balance) -> OrderEvent {
+        OrderEvent::Update {
+            account: AccountId::repeat_byte(byte),
+            index,
+            already_filled: 50_000_000,
+            status,
+            remaining,
+        }
+    }
+
+    #[test]
+    fn test_rebuild() {
+        let events = vec![
+            (1, put(1, 0, Side::Buy)),
+            (1, put(1, 1, Side::Sell)),
+            (1, put(2, 0, Side::Sell)),
+            (2, update(1, 0, OrderStatus::ParitialFill, 500)),
+            (2, update(2, 0, OrderStatus::ParitialFill, 50_000_000)),
+            (3, update(1, 1, OrderStatus::Canceled, 0)),
+            (3, update(3, 0, OrderStatus::Filled, 0)),
+        ];
+
+        let orders = rebuild(block_records(events));
+        assert_eq!(orders.len(), 2);
+        assert_eq!(orders[0].account, AccountId::repeat_byte(1));
+        assert_eq!(orders[0].remaining, Some(500));
+        assert_eq!(orders[1].account, AccountId::repeat_byte(2));
+        assert_eq!(orders[1].already_filled, 50_000_000);
+    }
+
+    #[test]
+    fn test_reserved() {
+        let pair = TradingPair {
+            id: 0,
+            assets: "PCX".to_string(),
+            currency: "BTC".to_string(),
+            precision: 9,
+            unit_precision: 2,
+            online: true,
+        };
+        let order = |side| rebuild(block_records(vec![(1, put(1, 0, side))])).remove(0);
+
+        // 1 PCX at 0.00001 BTC
+        assert_eq!(
+            reserved(&order(Side::Buy), &pair, 8),
+            ("BTC".to_string(), 1_000)
+        );
+        assert_eq!(
+            reserved(&order(Side::Sell), &pair, 8),
+            ("PCX".to_string(), 100_000_000)
+        );
+        let mut updated = order(Side::Buy);
+        updated.remaining = Some(300);
+        assert_eq!(reserved(&updated, &pair, 8), ("BTC".to_string(), 300));
+    }
+}
@@ -0,0 +1,285 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::chunk::{chunks, export_archive, load_archive_records, BlockRecord};
+use crate::{
+    archive_exists, decode_dynamic_events, decode_events_by_metadata, load_archive, save_archive,
+    BlockNumber, ChainX, ChainXEventRecord, Config, EventsDecoder, Metadata, TypeRegistry,
+};
+
+const ARCHIVE: &str = "events";
+
+/// The events of block #0 in the archive.
+const GENESIS: &str = "genesis.json";
+
+/// The event records of a block, the blocks without events are not exported.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+struct BlockEvents {
+    /// The spec version of the runtime, which picks the event layout of `data`.
+    spec_version: u32,
+    /// The raw `System Events` storage, which the event histories are decoded from.
+    data: String,
+    /// `{ "phase", "module", "event", "args", "topics" }` decoded by the runtime metadata, empty
+    /// if the metadata can't decode them.
+    events: Vec<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// Export the event records of all blocks up to the target height into `events/genesis.json`
+/// (block #0) and `events/[begin]-[end].json`, the existing chunks are skipped.
+///
+/// This is the only scan of the events of the chain, the event histories are picked from it.
+pub async fn export(conf: &Config) -> Result<()> {
+    if archive_exists(ARCHIVE, GENESIS)? {
+        log::info!("[{}] Block #0 already got", ARCHIVE);
+    } else {
+        let chainx = ChainX::connect(conf).await?;
+        let blocks = block_events(chainx, 0, vec![0]).await?;
+        save_archive(ARCHIVE, GENESIS, &blocks)?;
+    }
+    export_archive(conf, ARCHIVE, block_events).await
+}
+
+async fn block_events(
+    chainx: ChainX,
+    id: usize,
+    heights: Vec<BlockNumber>,
+) -> Result<Vec<BlockRecord<BlockEvents>>> {
+    let (begin, end) = (heights[0], heights[heights.len() - 1]);
+    // So that the blocks of the same runtime as the last block won't fetch the version again.
+    chainx.spec_version(end).await?;
+    let mut blocks = vec![];
+    for height in heights {
+        log::info!("[{}] [{}-{}] Block Height {}", id, begin, end, height);
+        let storage = match chainx.system_events(height).await? {
+            Some(storage) => storage,
+            None => continue,
+        };
+        let spec_version = chainx.spec_version(height).await?;
+        let metadata = chainx.runtime_metadata(height).await?;
+        let decoders = EventsDecoder::with_alternatives(spec_version);
+        let decoded = decode_dynamic_events(chainx.registry(), &metadata, &decoders, &storage.0);
+        let (events, error) = match decoded {
+            Ok((_, events)) => (events, None),
+            Err(err) => {
+                log::warn!("Block Height {}, err: {}", height, err);
+                (vec![], Some(err.to_string()))
+            }
+        };
+        blocks.push(BlockRecord {
+            height,
+            record: BlockEvents {
+                spec_version,
+                data: format!("0x{}", hex::encode(&storage.0)),
+                events,
+                error,
+            },
+        });
+    }
+    Ok(blocks)
+}
+
+/// Export the `events` archive up to the target height, and pick the records of all blocks from
+/// it in the order of the events. The typed `ChainXEvent`s are decoded from the raw events by
+/// the metadata of their runtimes.
+///
+/// Fails with the heights of all blocks whose events can't be decoded, so that no history is
+/// built on the incomplete events.
+pub(super) async fn pick_events<T, F>(conf: &Config, pick: F) -> Result<Vec<BlockRecord<T>>>
+where
+    F: Fn(&ChainXEventRecord) -> Option<T> + Copy,
+{
+    export(conf).await?;
+
+    let chainx = ChainX::connect(conf).await?;
+    let current = chainx.current_metadata().await?;
+    let mut records = vec![];
+    let mut failed = vec![];
+    // Block #0, then the chunks.
+    let ranges = std::iter::once(None).chain(chunks(1, conf.height).map(Some));
+    for range in ranges {
+        let blocks: Vec<BlockRecord<BlockEvents>> = match range {
+            None => load_archive(ARCHIVE, GENESIS)
+                .map_err(|err| anyhow!("[{}] {}: {}", ARCHIVE, GENESIS, err))?,
+            // The chunks exported before the raw events were kept can't be loaded, they have to
+            // be removed and exported again.
+            Some((begin, end)) => load_archive_records(ARCHIVE, begin, end).map_err(|err| {
+                anyhow!(
+                    "[{}] Block #{}-#{}: {}, remove the chunk and export it again",
+                    ARCHIVE,
+                    begin,
+                    end,
+                    err
+                )
+            })?,
+        };
+        for block in blocks {
+            let metadata = chainx
+                .spec_version_metadata(block.record.spec_version, Some(block.height))
+                .await?;
+            match pick_block_events(chainx.registry(), &metadata, &current, &block, pick) {
+                Ok(picked) => records.extend(picked),
+                Err(err) => {
+                    log::error!("Block Height {}, err: {}", block.height, err);
+                    failed.push(block.height);
+                }
+            }
+        }
+    }
+
+    if !failed.is_empty() {
+        bail!(
+            "The events of {} blocks can't be decoded: {:?}",
+            failed.len(),
+            failed
+        );
+    }
+    Ok(records)
+}
+
+/// Decode the events of a block by the metadata of its runtime, and keep the records picked by
+/// `pick`.
+///
+/// Fails if some events are unknown to the current runtime, since `pick` may miss them.
+fn pick_block_events<T, F>(
+    registry: &TypeRegistry,
+    metadata: &Metadata,
+    current: &Metadata,
+    block: &BlockRecord<BlockEvents>,
+    pick: F,
+) -> Result<Vec<BlockRecord<T>>>
+where
+    F: Fn(&ChainXEventRecord) -> Option<T>,
+{
+    let data = hex::decode(block.record.data.trim_start_matches("0x"))?;
+    let decoders = EventsDecoder::with_alternatives(block.record.spec_version);
+    let (_, events) = decode_events_by_metadata(registry, metadata, current, &decoders, &data)?;
+    if !events.skipped.is_empty() {
+        let skipped = events
+            .skipped
+            .iter()
+            .map(|index| match block.record.events.get(*index as usize) {
+                Some(event) => format!(
+                    "#{} {}::{}",
+                    index,
+                    event["module"].as_str().unwrap_or_default(),
+                    event["event"].as_str().unwrap_or_default()
+                ),
+                None => format!("#{}", index),
+            })
+            .collect::<Vec<_>>();
+        bail!(
+            "Events of spec version {} unknown to the current runtime: {}",
+            block.record.spec_version,
+            skipped.join(", ")
+        );
+    }
+    Ok(events
+        .records
+        .iter()
+        .filter_map(pick)
+        .map(|record| BlockRecord {
+            height: block.height,
+            record,
+        })
+        .collect())
+}
+
+/// The `System Events` of the event histories, in the format of the `events` archive.
+#[cfg(test)]
+const FIXTURE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/events.json");
+
+/// Decode the blocks of the history in the fixture by their record layouts, and keep the records
+/// picked by `pick`, for the tests of the event histories.
+#[cfg(test)]
+pub(crate) fn fixture_events<T, F>(history: &str, pick: F) -> Vec<BlockRecord<T>>
+where
+    F: Fn(&ChainXEventRecord) -> Option<T>,
+{
+    let file = std::fs::File::open(FIXTURE).expect("events fixture must exist; qed");
+    let mut fixture: std::collections::BTreeMap<String, Vec<BlockRecord<BlockEvents>>> =
+        serde_json::from_reader(file).expect("events fixture must be valid; qed");
+    let blocks = fixture
+        .remove(history)
+        .unwrap_or_else(|| panic!("No {} in the events fixture", history));
+    let mut records = vec![];
+    for block in blocks {
+        let data = hex::decode(block.record.data.trim_start_matches("0x")).unwrap();
+        let decoders = EventsDecoder::with_alternatives(block.record.spec_version);
+        let (_, events) = crate::decode_events(&decoders, &data)
+            .unwrap_or_else(|err| panic!("Block Height {}, err: {}", block.height, err));
+        records.extend(
+            events
+                .records
+                .iter()
+                .filter_map(&pick)
+                .map(|record| BlockRecord {
+                    height: block.height,
+                    record,
+                }),
+        );
+    }
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{current_metadata, AccountId, ChainXEvent, EventRecord, Hash, Phase, XAssetsEvent};
+    use codec::Encode;
+
+    fn block(height: BlockNumber, data: Vec<u8>) -> BlockRecord<BlockEvents> {
+        BlockRecord {
+            height,
+            record: BlockEvents {
+                spec_version: 0,
+                data: format!("0x{}", hex::encode(data)),
+                events: vec![],
+                error: None,
+            },
+        }
+    }
+
+    fn new_account(record: &ChainXEventRecord) -> Option<AccountId> {
+        match &record.event {
+            ChainXEvent::XAssets(XAssetsEvent::NewAccount(account)) => Some(*account),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_pick_block_events() {
+        let records = vec![EventRecord {
+            phase: Phase::ApplyExtrinsic(1),
+            event: ChainXEvent::XAssets(XAssetsEvent::NewAccount(AccountId::repeat_byte(1))),
+            topics: Vec::<Hash>::new(),
+        }];
+        let registry = TypeRegistry::default();
+        let current = current_metadata();
+        let pick = |block: &BlockRecord<BlockEvents>| {
+            pick_block_events(&registry, &current, &current, block, new_account)
+        };
+        let picked = pick(&block(7, records.encode())).unwrap();
+        assert_eq!(
+            picked,
+            vec![BlockRecord {
+                height: 7,
+                record: AccountId::repeat_byte(1),
+            }]
+        );
+
+        assert!(pick(&block(8, vec![0xff])).is_err());
+
+        // [ApplyExtrinsic(1), XAssets::Unknown(5), no topics] of a runtime, which is skipped
+        // since the current runtime doesn't know it.
+        let metadata = Metadata::with_events(&[("XAssets", &[("Unknown", &["u32"])])]);
+        let mut data = codec::Compact(1u32).encode();
+        (0u8, 1u32, 0u8, 0u8, 5u32, 0u8).encode_to(&mut data);
+        let unknown = block(9, data);
+        assert!(pick_block_events(&registry, &metadata, &current, &unknown, new_account).is_err());
+    }
+}
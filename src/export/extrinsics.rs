@@ -0,0 +1,115 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::chunk::{chunk_filename, chunks, export_archive, full_chunk_filename};
+use crate::{
+    archive_exists, load_archive, Acceleration, Address, BlockNumber, ChainX, Config, Index,
+};
+
+const ARCHIVE: &str = "extrinsics";
+
+/// A signed extrinsic, the unsigned ones (like `Timestamp::set`) are not exported.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub(super) struct SignedExtrinsic {
+    pub height: BlockNumber,
+    pub index: u32,
+    /// `{ "id": account }` or `{ "index": account index }`.
+    pub signer: Value,
+    nonce: Index,
+    acceleration: Acceleration,
+    /// `{ "module", "call", "args" }` decoded by the runtime metadata, or the raw call with the
+    /// decode error.
+    pub call: Value,
+    /// `None` if the result is unknown since the events of the block can't be decoded.
+    success: Option<bool>,
+}
+
+/// Export the signed extrinsics of all blocks up to the target height into
+/// `extrinsics/[begin]-[end].json`, the existing chunks are skipped.
+pub async fn export(conf: &Config) -> Result<()> {
+    export_archive(conf, ARCHIVE, signed_extrinsics).await
+}
+
+/// Export the `extrinsics` archive up to the target height, and pick the extrinsics of all
+/// blocks from it by chunks, so that only the picked ones are kept in memory.
+pub(super) async fn pick_extrinsics<T, F>(conf: &Config, pick: F) -> Result<Vec<T>>
+where
+    F: Fn(SignedExtrinsic) -> Option<T>,
+{
+    export(conf).await?;
+
+    let mut picked = vec![];
+    for (begin, end) in chunks(1, conf.height) {
+        let filename = if archive_exists(ARCHIVE, chunk_filename(begin, end))? {
+            chunk_filename(begin, end)
+        } else {
+            full_chunk_filename(begin)
+        };
+        let extrinsics: Vec<SignedExtrinsic> = load_archive(ARCHIVE, filename)?;
+        picked.extend(
+            extrinsics
+                .into_iter()
+                .filter(|extrinsic| begin <= extrinsic.height && extrinsic.height <= end)
+                .filter_map(&pick),
+        );
+    }
+    Ok(picked)
+}
+
+async fn signed_extrinsics(
+    chainx: ChainX,
+    id: usize,
+    heights: Vec<BlockNumber>,
+) -> Result<Vec<SignedExtrinsic>> {
+    let (begin, end) = (heights[0], heights[heights.len() - 1]);
+    let mut extrinsics = vec![];
+    for height in heights {
+        log::info!("[{}] [{}-{}] Block Height {}", id, begin, end, height);
+        for extrinsic in chainx.block_extrinsics(height).await? {
+            let signature = match extrinsic.extrinsic.signature {
+                Some(signature) => signature,
+                None => continue,
+            };
+            // Only the decode errors are kept in the archive, the rpc errors are returned.
+            let metadata = chainx.runtime_metadata(height).await?;
+            let call = match chainx
+                .registry()
+                .decode_call(&metadata, &mut extrinsic.extrinsic.call.as_slice())
+            {
+                Ok(call) => call,
+                Err(err) => {
+                    log::warn!(
+                        "Block Height {}, extrinsic {}, err: {}",
+                        height,
+                        extrinsic.index,
+                        err
+                    );
+                    json!({
+                        "data": format!("0x{}", hex::encode(&extrinsic.extrinsic.call)),
+                        "error": err.to_string(),
+                    })
+                }
+            };
+            extrinsics.push(SignedExtrinsic {
+                height,
+                index: extrinsic.index,
+                signer: signer_json(&signature.signer),
+                nonce: signature.nonce,
+                acceleration: signature.acceleration,
+                call,
+                success: extrinsic.success,
+            });
+        }
+    }
+    Ok(extrinsics)
+}
+
+fn signer_json(signer: &Address) -> Value {
+    match signer {
+        Address::Id(account) => json!({ "id": account }),
+        Address::Index(index) => json!({ "index": index }),
+    }
+}
@@ -1,16 +1,13 @@
 // Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
 
 use anyhow::Result;
-use chainx_state_exporter::*;
 
-#[async_std::main]
-async fn main() -> Result<()> {
-    env_logger::init();
+use crate::{save_state, ChainX, Config};
 
-    let conf = CmdConfig::init()?;
+pub async fn export(conf: &Config) -> Result<()> {
     let height = conf.height;
 
-    let chainx = ChainX::new(&conf.chainx_ws_url).await?;
+    let chainx = ChainX::connect(conf).await?;
     let hash = chainx.block_hash(Some(height)).await?;
     log::info!("Block Height {}, Hash: {:?}", height, hash);
 
@@ -0,0 +1,272 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+use std::collections::BTreeMap;
+
+use anyhow::{ensure, Result};
+use serde::{Deserialize, Serialize};
+
+use super::assets::AccountWithAssets;
+use super::{chunk::BlockRecord, events::pick_events};
+use crate::{
+    load_state, save_state, AccountId, Balance, BlockNumber, ChainXEvent, ChainXEventRecord,
+    Config, Hash, TotalDepositWeightInfoV1, XBitcoinLockupRawEvent,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+enum LockupEvent {
+    /// `XBitcoinLockup::Lock`
+    Lock {
+        account: AccountId,
+        value: u64,
+        txid: Hash,
+        vout: u32,
+        /// The hex output script.
+        script: String,
+    },
+    /// `XBitcoinLockup::Unlock` or `XBitcoinLockup::UnlockedFromRoot`
+    Unlock { txid: Hash, vout: u32 },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct LockedOutput {
+    txid: Hash,
+    vout: u32,
+    value: u64,
+    account: AccountId,
+    script: String,
+    /// The block of the `Lock`.
+    height: BlockNumber,
+}
+
+/// An `Unlock` of an output that was never locked.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct UnknownUnlock {
+    txid: Hash,
+    vout: u32,
+    /// The block of the `Unlock`.
+    height: BlockNumber,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct LbtcLockups {
+    /// The sum of the still-locked outputs.
+    total_locked: Balance,
+    outputs: Vec<LockedOutput>,
+    unknown_unlocks: Vec<UnknownUnlock>,
+}
+
+const OUTPUT: &str = "lbtc-lockups.json";
+
+/// Export the still-locked L-BTC outputs at the target height into `lbtc-lockups.json`.
+pub async fn export(conf: &Config) -> Result<()> {
+    let height = conf.height;
+
+    let events = pick_events(conf, lockup_event).await?;
+    let (outputs, unknown_unlocks) = replay(events);
+    let total_locked = outputs.iter().map(|output| output.value).sum::<Balance>();
+    log::info!(
+        "Locked Output Number: {}, Total Locked: {}, Unknown Unlock Number: {}",
+        outputs.len(),
+        total_locked,
+        unknown_unlocks.len()
+    );
+
+    let value = LbtcLockups {
+        total_locked,
+        outputs,
+        unknown_unlocks,
+    };
+    save_state(height, OUTPUT, &value)?;
+
+    Ok(())
+}
+
+/// Verify the total of the still-locked outputs against the `L-BTC` of `assets.json` and the
+/// circulation in `deposit-weight-nodes.json`, and that no unknown output was unlocked.
+pub fn verify(conf: &Config) -> Result<()> {
+    let height = conf.height;
+
+    let lockups: LbtcLockups = load_state(height, OUTPUT)?;
+    for unlock in &lockups.unknown_unlocks {
+        log::error!(
+            "Block Height {}, Unlock an unknown output {:?}:{}",
+            unlock.height,
+            unlock.txid,
+            unlock.vout
+        );
+    }
+
+    let assets_accounts: Vec<AccountWithAssets> = load_state(height, "assets.json")?;
+    let total_account_lbtc = assets_accounts
+        .iter()
+        .flat_map(|account| account.assets.iter())
+        .filter(|asset| asset.name == "L-BTC")
+        .flat_map(|asset| asset.details.values())
+        .sum::<Balance>();
+    let node_deposit_weight: TotalDepositWeightInfoV1 =
+        load_state(height, "deposit-weight-nodes.json")?;
+    let circulation = node_deposit_weight.lbtc.balance;
+    log::info!(
+        "Total Locked: {}, Total L-BTC of Accounts: {}, L-BTC Circulation: {}",
+        lockups.total_locked,
+        total_account_lbtc,
+        circulation
+    );
+    ensure!(
+        lockups.total_locked == total_account_lbtc && lockups.total_locked == circulation,
+        "The locked outputs ({}) mismatch the L-BTC of accounts ({}) or the circulation ({})",
+        lockups.total_locked,
+        total_account_lbtc,
+        circulation
+    );
+    ensure!(
+        lockups.unknown_unlocks.is_empty(),
+        "{} unknown outputs are unlocked",
+        lockups.unknown_unlocks.len()
+    );
+
+    Ok(())
+}
+
+fn lockup_event(record: &ChainXEventRecord) -> Option<LockupEvent> {
+    match &record.event {
+        ChainXEvent::XBitcoinLockup(XBitcoinLockupRawEvent::Lock(
+            account,
+            value,
+            txid,
+            vout,
+            script,
+        )) => Some(LockupEvent::Lock {
+            account: *account,
+            value: *value,
+            txid: *txid,
+            vout: *vout,
+            script: format!("0x{}", hex::encode(script)),
+        }),
+        // `Unlock(unlock txid, unlock vout, spent txid, spent vout)`, the locked output is the
+        // spent one.
+        ChainXEvent::XBitcoinLockup(XBitcoinLockupRawEvent::Unlock(_, _, txid, vout))
+        | ChainXEvent::XBitcoinLockup(XBitcoinLockupRawEvent::UnlockedFromRoot(txid, vout)) => {
+            Some(LockupEvent::Unlock {
+                txid: *txid,
+                vout: *vout,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Replay the events in order, the outputs locked and not unlocked are still locked.
+///
+/// Also returns the unlocks of the outputs that were never locked.
+fn replay(events: Vec<BlockRecord<LockupEvent>>) -> (Vec<LockedOutput>, Vec<UnknownUnlock>) {
+    let mut outputs = BTreeMap::new();
+    let mut unknown_unlocks = vec![];
+    for BlockRecord { height, record } in events {
+        match record {
+            LockupEvent::Lock {
+                account,
+                value,
+                txid,
+                vout,
+                script,
+            } => {
+                outputs.insert(
+                    (txid, vout),
+                    LockedOutput {
+                        txid,
+                        vout,
+                        value,
+                        account,
+                        script,
+                        height,
+                    },
+                );
+            }
+            LockupEvent::Unlock { txid, vout } => {
+                if outputs.remove(&(txid, vout)).is_none() {
+                    log::warn!("Unlock an unknown output {:?}:{}", txid, vout);
+                    unknown_unlocks.push(UnknownUnlock { txid, vout, height });
+                }
+            }
+        }
+    }
+    let outputs = outputs.into_iter().map(|(_, output)| output).collect();
+    (outputs, unknown_unlocks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::chunk::block_records;
+    use crate::{EventRecord, Phase};
+
+    fn lockup_record(event: XBitcoinLockupRawEvent<AccountId>) -> ChainXEventRecord {
+        EventRecord {
+            phase: Phase::ApplyExtrinsic(1),
+            event: ChainXEvent::XBitcoinLockup(event),
+            topics: vec![],
+        }
+    }
+
+    fn lock(byte: u8, vout: u32, value: u64) -> ChainXEventRecord {
+        lockup_record(XBitcoinLockupRawEvent::Lock(
+            AccountId::repeat_byte(byte),
+            value,
+            Hash::repeat_byte(byte),
+            vout,
+            vec![0x6a],
+        ))
+    }
+
+    /// Spend the locked output `txid:vout` by the unlock transaction `0xff..:0`.
+    fn unlock(byte: u8, vout: u32) -> ChainXEventRecord {
+        lockup_record(XBitcoinLockupRawEvent::Unlock(
+            Hash::repeat_byte(0xff),
+            0,
+            Hash::repeat_byte(byte),
+            vout,
+        ))
+    }
+
+    #[test]
+    fn test_replay() {
+        let events = vec![
+            (1, lock(1, 0, 100)),
+            (1, lock(1, 1, 200)),
+            (2, lock(2, 0, 300)),
+            (2, lock(4, 0, 400)),
+            (3, unlock(1, 0)),
+            (4, unlock(3, 0)),
+            (
+                5,
+                lockup_record(XBitcoinLockupRawEvent::UnlockedFromRoot(
+                    Hash::repeat_byte(4),
+                    0,
+                )),
+            ),
+        ];
+        let events = events
+            .into_iter()
+            .filter_map(|(height, record)| Some((height, lockup_event(&record)?)))
+            .collect();
+
+        let (outputs, unknown_unlocks) = replay(block_records(events));
+        assert_eq!(outputs.len(), 2);
+        assert_eq!(
+            (outputs[0].txid, outputs[0].vout),
+            (Hash::repeat_byte(1), 1)
+        );
+        assert_eq!(outputs[0].script, "0x6a");
+        assert_eq!(outputs[1].value, 300);
+        assert_eq!(outputs[1].height, 2);
+        assert_eq!(
+            unknown_unlocks,
+            vec![UnknownUnlock {
+                txid: Hash::repeat_byte(3),
+                vout: 0,
+                height: 4,
+            }]
+        );
+    }
+}
@@ -0,0 +1,223 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+mod accounts;
+mod address_bindings;
+mod assets;
+mod btc_deposits;
+mod channel_bindings;
+mod chunk;
+mod deposit_weight;
+mod dex_orders;
+mod events;
+mod extrinsics;
+mod intentions;
+mod lbtc_lockups;
+mod multisigs;
+mod rewards;
+mod sdot_claims;
+mod session_index;
+mod sessions;
+mod trustee_sessions;
+mod validator_offences;
+mod vote_weight;
+mod withdrawals;
+
+use anyhow::Result;
+
+pub use self::accounts::retry_missing;
+
+use crate::{state_exists, Config};
+
+/// The stages of exporting and verifying the ChainX 1.0 state.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Stage {
+    SessionIndex,
+    Accounts,
+    AccountIndices,
+    Intentions,
+    Assets,
+    VerifyAssets,
+    DepositWeight,
+    VoteWeight,
+    VerifyVoteWeight,
+    // The stages below scan all blocks, so they are not a part of the full export.
+    Extrinsics,
+    Events,
+    Withdrawals,
+    BtcDeposits,
+    VerifyBtcDeposits,
+    AddressBindings,
+    TrusteeSessions,
+    ChannelBindings,
+    DexOrders,
+    VerifyDexOrders,
+    SdotClaims,
+    LbtcLockups,
+    VerifyLbtcLockups,
+    Multisigs,
+    ValidatorOffences,
+    Sessions,
+    Rewards,
+}
+
+impl Stage {
+    /// The stages of a full export, including the verifications.
+    pub const ALL: &'static [Stage] = &[
+        Stage::SessionIndex,
+        Stage::Accounts,
+        Stage::AccountIndices,
+        Stage::Intentions,
+        Stage::Assets,
+        Stage::VerifyAssets,
+        Stage::DepositWeight,
+        Stage::VoteWeight,
+        Stage::VerifyVoteWeight,
+    ];
+
+    /// The stages whose outputs are required by this stage.
+    pub fn dependencies(self) -> &'static [Stage] {
+        match self {
+            Stage::SessionIndex | Stage::Accounts | Stage::Intentions => &[],
+            Stage::Extrinsics | Stage::Events => &[],
+            Stage::Withdrawals | Stage::BtcDeposits | Stage::AddressBindings => &[],
+            Stage::TrusteeSessions | Stage::ChannelBindings | Stage::Multisigs => &[],
+            Stage::LbtcLockups => &[],
+            Stage::AccountIndices => &[Stage::Accounts],
+            Stage::Assets | Stage::DepositWeight | Stage::VoteWeight => &[Stage::Accounts],
+            Stage::VerifyAssets => &[Stage::Assets],
+            Stage::VerifyVoteWeight => &[Stage::VoteWeight],
+            Stage::VerifyBtcDeposits => &[Stage::BtcDeposits, Stage::Withdrawals, Stage::Assets],
+            // Both take the tokens from `assets-total.json`.
+            Stage::DexOrders => &[Stage::Assets],
+            Stage::SdotClaims => &[Stage::Assets],
+            Stage::VerifyDexOrders => &[Stage::DexOrders],
+            Stage::VerifyLbtcLockups => &[Stage::LbtcLockups, Stage::Assets, Stage::DepositWeight],
+            Stage::ValidatorOffences => &[Stage::Intentions],
+            Stage::Sessions => &[Stage::SessionIndex],
+            Stage::Rewards => &[Stage::AccountIndices],
+        }
+    }
+
+    /// The state file produced by this stage, the verifications produce nothing and the archives
+    /// (like `extrinsics`) skip their existing chunks by themselves.
+    pub fn output(self) -> Option<&'static str> {
+        match self {
+            Stage::SessionIndex => Some("session-index.json"),
+            Stage::Accounts => Some("accounts.json"),
+            Stage::AccountIndices => Some("account-indices.json"),
+            Stage::Intentions => Some("intentions.json"),
+            Stage::Assets => Some("assets.json"),
+            Stage::DepositWeight => Some("deposit-weight-accounts.json"),
+            Stage::VoteWeight => Some("vote-weight-accounts.json"),
+            Stage::Withdrawals => Some("withdrawals.json"),
+            Stage::BtcDeposits => Some("btc-deposits.json"),
+            Stage::AddressBindings => Some("address-bindings.json"),
+            Stage::TrusteeSessions => Some("trustee-sessions.json"),
+            Stage::ChannelBindings => Some("channel-bindings.json"),
+            Stage::DexOrders => Some("dex-orders.json"),
+            Stage::SdotClaims => Some("sdot-claims.json"),
+            Stage::LbtcLockups => Some("lbtc-lockups.json"),
+            Stage::Multisigs => Some("multisigs.json"),
+            Stage::ValidatorOffences => Some("validator-offences.json"),
+            Stage::Sessions => Some("sessions.json"),
+            Stage::Rewards => Some("rewards.json"),
+            Stage::VerifyAssets | Stage::VerifyVoteWeight => None,
+            Stage::VerifyBtcDeposits | Stage::VerifyDexOrders => None,
+            Stage::VerifyLbtcLockups => None,
+            Stage::Extrinsics | Stage::Events => None,
+        }
+    }
+
+    async fn run(self, conf: &Config) -> Result<()> {
+        match self {
+            Stage::SessionIndex => session_index::export(conf).await,
+            Stage::Accounts => accounts::export(conf).await,
+            Stage::AccountIndices => accounts::export_indices(conf).await,
+            Stage::Intentions => intentions::export(conf).await,
+            Stage::Assets => assets::export(conf).await,
+            Stage::VerifyAssets => assets::verify(conf),
+            Stage::DepositWeight => deposit_weight::export(conf).await,
+            Stage::VoteWeight => vote_weight::export(conf).await,
+            Stage::VerifyVoteWeight => vote_weight::verify(conf),
+            Stage::Extrinsics => extrinsics::export(conf).await,
+            Stage::Events => events::export(conf).await,
+            Stage::Withdrawals => withdrawals::export(conf).await,
+            Stage::BtcDeposits => btc_deposits::export(conf).await,
+            Stage::VerifyBtcDeposits => btc_deposits::verify(conf),
+            Stage::AddressBindings => address_bindings::export(conf).await,
+            Stage::TrusteeSessions => trustee_sessions::export(conf).await,
+            Stage::ChannelBindings => channel_bindings::export(conf).await,
+            Stage::DexOrders => dex_orders::export(conf).await,
+            Stage::VerifyDexOrders => dex_orders::verify(conf),
+            Stage::SdotClaims => sdot_claims::export(conf).await,
+            Stage::LbtcLockups => lbtc_lockups::export(conf).await,
+            Stage::VerifyLbtcLockups => lbtc_lockups::verify(conf),
+            Stage::Multisigs => multisigs::export(conf).await,
+            Stage::ValidatorOffences => validator_offences::export(conf).await,
+            Stage::Sessions => sessions::export(conf).await,
+            Stage::Rewards => rewards::export(conf).await,
+        }
+    }
+}
+
+/// Run the target stages and their dependencies in the dependency order.
+///
+/// The stages whose outputs already exist are skipped, unless it's a target stage and `force`
+/// is set.
+pub async fn run_stages(conf: &Config, targets: &[Stage], force: bool) -> Result<()> {
+    let mut stages = vec![];
+    for target in targets {
+        resolve(*target, &mut stages);
+    }
+
+    for stage in stages {
+        let forced = force && targets.contains(&stage);
+        if let Some(output) = stage.output() {
+            if !forced && state_exists(conf.height, output)? {
+                log::info!("[{:?}] {} already got, skipped", stage, output);
+                continue;
+            }
+        }
+        log::info!("[{:?}] Running (Block Height {})", stage, conf.height);
+        stage.run(conf).await?;
+        log::info!("[{:?}] Done", stage);
+    }
+    Ok(())
+}
+
+fn resolve(stage: Stage, stages: &mut Vec<Stage>) {
+    if stages.contains(&stage) {
+        return;
+    }
+    for dependency in stage.dependencies() {
+        resolve(*dependency, stages);
+    }
+    stages.push(stage);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve() {
+        let mut stages = vec![];
+        resolve(Stage::VerifyVoteWeight, &mut stages);
+        resolve(Stage::Assets, &mut stages);
+        assert_eq!(
+            stages,
+            vec![
+                Stage::Accounts,
+                Stage::VoteWeight,
+                Stage::VerifyVoteWeight,
+                Stage::Assets
+            ]
+        );
+
+        let mut stages = vec![];
+        for stage in Stage::ALL {
+            resolve(*stage, &mut stages);
+        }
+        assert_eq!(stages, Stage::ALL);
+    }
+}
@@ -0,0 +1,360 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+
+use super::{chunk::BlockRecord, events::pick_events};
+use crate::{
+    save_state, AccountId, AddrType, BlockNumber, ChainX, ChainXEvent, ChainXEventRecord, Config,
+    Hash, XMultisigRawEvent,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+enum MultisigEvent {
+    /// `XMultisig::DeployMultiSig`
+    Deploy {
+        deployer: AccountId,
+        multisig: AccountId,
+        owner_number: u32,
+        required: u32,
+    },
+    /// `XMultisig::ExecMultiSig`, a proposal is submitted.
+    Exec {
+        proposer: AccountId,
+        multisig: AccountId,
+        proposal_id: Hash,
+    },
+    /// `XMultisig::Confirm`, the proposal is executed once no more confirmation is needed.
+    Confirm {
+        multisig: AccountId,
+        proposal_id: Hash,
+        yet_needed: u32,
+    },
+    /// `XMultisig::RemoveMultiSigIdFor`
+    Remove {
+        multisig: AccountId,
+        proposal_id: Hash,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct Owner {
+    account: AccountId,
+    is_admin: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct Proposal {
+    id: Hash,
+    proposer: AccountId,
+    /// The block of the `ExecMultiSig`.
+    height: BlockNumber,
+    yet_needed: u32,
+    /// The bits of the owners who have confirmed.
+    owners_done: u32,
+    /// `{ "module", "call", "args" }` decoded by the runtime metadata of the `ExecMultiSig`, or
+    /// the raw call with the decode error.
+    call: Value,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+struct Multisig {
+    address: AccountId,
+    deployer: AccountId,
+    /// The block of the `DeployMultiSig`.
+    height: BlockNumber,
+    addr_type: Option<AddrType>,
+    required: u32,
+    /// Empty if the `MultiSigAddrInfo` is not found.
+    owners: Vec<Owner>,
+    /// The proposals still pending at the height.
+    pending: Vec<Proposal>,
+}
+
+/// A proposal which is neither executed nor removed by the events.
+struct PendingProposal {
+    multisig: AccountId,
+    id: Hash,
+    proposer: AccountId,
+    height: BlockNumber,
+}
+
+/// Export all multisig addresses with their owners and pending proposals at the target height
+/// into `multisigs.json`.
+///
+/// The multisigs and proposals are rebuilt from the events, the owners are taken from the
+/// `XMultiSig` storages at the height when they exist. The proposals are only exported when they
+/// are still in the `XMultiSig PendingStateFor` storage, with its confirmations and call.
+pub async fn export(conf: &Config) -> Result<()> {
+    let height = conf.height;
+
+    let events = pick_events(conf, multisig_event).await?;
+    let (deploys, proposals) = replay(events);
+
+    let chainx = ChainX::connect(conf).await?;
+    let hash = chainx.block_hash(Some(height)).await?;
+    let mut multisigs = BTreeMap::new();
+    for (address, (deploy_height, deployer, owner_number, required)) in deploys {
+        let info = chainx.multisig_addr_info(&address, hash).await?;
+        if info.is_none() {
+            log::warn!(
+                "Multisig {:?} has no MultiSigAddrInfo, {} owners are unknown",
+                address,
+                owner_number
+            );
+        }
+        let multisig = Multisig {
+            address,
+            deployer,
+            height: deploy_height,
+            addr_type: info.as_ref().map(|info| info.addr_type),
+            required: info.as_ref().map_or(required, |info| info.required_num),
+            owners: info
+                .map(|info| {
+                    info.owner_list
+                        .into_iter()
+                        .map(|(account, is_admin)| Owner { account, is_admin })
+                        .collect()
+                })
+                .unwrap_or_default(),
+            pending: vec![],
+        };
+        multisigs.insert(address, multisig);
+    }
+
+    for proposal in proposals {
+        let state = chainx
+            .multisig_pending_state(&proposal.multisig, &proposal.id, hash)
+            .await?;
+        let state = match state {
+            Some(state) => state,
+            None => {
+                log::warn!(
+                    "Proposal {:?} of multisig {:?} is not pending in the storage",
+                    proposal.id,
+                    proposal.multisig
+                );
+                continue;
+            }
+        };
+        let call = decode_call(&chainx, proposal.height, &state.proposal).await?;
+        let multisig = match multisigs.get_mut(&proposal.multisig) {
+            Some(multisig) => multisig,
+            None => {
+                log::warn!(
+                    "Proposal {:?} of an unknown multisig {:?}",
+                    proposal.id,
+                    proposal.multisig
+                );
+                continue;
+            }
+        };
+        multisig.pending.push(Proposal {
+            id: proposal.id,
+            proposer: proposal.proposer,
+            height: proposal.height,
+            yet_needed: state.yet_needed,
+            owners_done: state.owners_done,
+            call,
+        });
+    }
+
+    let multisigs = multisigs
+        .into_iter()
+        .map(|(_, multisig)| multisig)
+        .collect::<Vec<_>>();
+    log::info!(
+        "Multisig Number: {}, Pending Proposal Number: {}",
+        multisigs.len(),
+        multisigs
+            .iter()
+            .map(|multisig| multisig.pending.len())
+            .sum::<usize>()
+    );
+
+    save_state(height, "multisigs.json", &multisigs)?;
+
+    Ok(())
+}
+
+async fn decode_call(chainx: &ChainX, height: BlockNumber, proposal: &[u8]) -> Result<Value> {
+    let metadata = chainx.runtime_metadata(height).await?;
+    Ok(
+        match chainx.registry().decode_call(&metadata, &mut &proposal[..]) {
+            Ok(call) => call,
+            Err(err) => json!({
+                "data": format!("0x{}", hex::encode(proposal)),
+                "error": err.to_string(),
+            }),
+        },
+    )
+}
+
+fn multisig_event(record: &ChainXEventRecord) -> Option<MultisigEvent> {
+    match &record.event {
+        ChainXEvent::XMultisig(XMultisigRawEvent::DeployMultiSig(
+            deployer,
+            multisig,
+            owner_number,
+            required,
+        )) => Some(MultisigEvent::Deploy {
+            deployer: *deployer,
+            multisig: *multisig,
+            owner_number: *owner_number,
+            required: *required,
+        }),
+        ChainXEvent::XMultisig(XMultisigRawEvent::ExecMultiSig(
+            proposer,
+            multisig,
+            proposal_id,
+            _,
+        )) => Some(MultisigEvent::Exec {
+            proposer: *proposer,
+            multisig: *multisig,
+            proposal_id: *proposal_id,
+        }),
+        ChainXEvent::XMultisig(XMultisigRawEvent::Confirm(
+            multisig,
+            proposal_id,
+            yet_needed,
+            _,
+        )) => Some(MultisigEvent::Confirm {
+            multisig: *multisig,
+            proposal_id: *proposal_id,
+            yet_needed: *yet_needed,
+        }),
+        ChainXEvent::XMultisig(XMultisigRawEvent::RemoveMultiSigIdFor(multisig, proposal_id)) => {
+            Some(MultisigEvent::Remove {
+                multisig: *multisig,
+                proposal_id: *proposal_id,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Replay the events in order into the deployed multisigs (by the addresses: height, deployer,
+/// owner number and required confirmations) and the proposals still pending.
+#[allow(clippy::type_complexity)]
+fn replay(
+    events: Vec<BlockRecord<MultisigEvent>>,
+) -> (
+    BTreeMap<AccountId, (BlockNumber, AccountId, u32, u32)>,
+    Vec<PendingProposal>,
+) {
+    let mut deploys = BTreeMap::new();
+    let mut proposals = BTreeMap::new();
+    for BlockRecord { height, record } in events {
+        match record {
+            MultisigEvent::Deploy {
+                deployer,
+                multisig,
+                owner_number,
+                required,
+            } => {
+                deploys.insert(multisig, (height, deployer, owner_number, required));
+            }
+            MultisigEvent::Exec {
+                proposer,
+                multisig,
+                proposal_id,
+            } => {
+                proposals.insert(
+                    (multisig, proposal_id),
+                    PendingProposal {
+                        multisig,
+                        id: proposal_id,
+                        proposer,
+                        height,
+                    },
+                );
+            }
+            MultisigEvent::Confirm {
+                multisig,
+                proposal_id,
+                yet_needed,
+            } => {
+                if yet_needed == 0 {
+                    proposals.remove(&(multisig, proposal_id));
+                }
+            }
+            MultisigEvent::Remove {
+                multisig,
+                proposal_id,
+            } => {
+                proposals.remove(&(multisig, proposal_id));
+            }
+        }
+    }
+    (deploys, proposals.into_iter().map(|(_, p)| p).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{chunk::block_records, events::fixture_events};
+
+    #[test]
+    fn test_replay() {
+        let multisig = AccountId::repeat_byte(0xaa);
+        let exec = |id| MultisigEvent::Exec {
+            proposer: AccountId::repeat_byte(1),
+            multisig,
+            proposal_id: Hash::repeat_byte(id),
+        };
+        let confirm = |id, yet_needed| MultisigEvent::Confirm {
+            multisig,
+            proposal_id: Hash::repeat_byte(id),
+            yet_needed,
+        };
+        let events = vec![
+            (
+                0,
+                MultisigEvent::Deploy {
+                    deployer: AccountId::repeat_byte(1),
+                    multisig,
+                    owner_number: 3,
+                    required: 2,
+                },
+            ),
+            (1, exec(1)),
+            (2, confirm(1, 1)),
+            (3, exec(2)),
+            (4, confirm(2, 1)),
+            (5, confirm(2, 0)),
+            (6, exec(3)),
+            (
+                7,
+                MultisigEvent::Remove {
+                    multisig,
+                    proposal_id: Hash::repeat_byte(3),
+                },
+            ),
+            (8, exec(4)),
+        ];
+
+        let (deploys, proposals) = replay(block_records(events));
+        assert_eq!(deploys[&multisig], (0, AccountId::repeat_byte(1), 3, 2));
+        assert_eq!(proposals.len(), 2);
+        assert_eq!(proposals[0].id, Hash::repeat_byte(1));
+        assert_eq!(proposals[0].height, 1);
+        assert_eq!(proposals[1].id, Hash::repeat_byte(4));
+        assert_eq!(proposals[1].height, 8);
+    }
+
+    #[test]
+    fn test_fixture() {
+        let multisig = AccountId::repeat_byte(0xaa);
+        let (deploys, proposals) = replay(fixture_events("multisigs", multisig_event));
+        assert_eq!(deploys.len(), 1);
+        assert_eq!(deploys[&multisig], (700, AccountId::repeat_byte(1), 3, 2));
+        assert_eq!(proposals.len(), 1);
+        assert_eq!(proposals[0].multisig, multisig);
+        assert_eq!(proposals[0].id, Hash::repeat_byte(1));
+        assert_eq!(proposals[0].proposer, AccountId::repeat_byte(1));
+        assert_eq!(proposals[0].height, 800);
+    }
+}
@@ -0,0 +1,652 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+use std::collections::BTreeMap;
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use super::accounts::AccountWithIndex;
+use super::extrinsics::{pick_extrinsics, SignedExtrinsic};
+use super::{chunk::BlockRecord, events::pick_events};
+use crate::{
+    load_state, save_state, AccountId, AccountIndex, Balance, BlockNumber, ChainXEvent,
+    ChainXEventRecord, Config, Hash, Phase, XMultisigRawEvent, XStakingRawEvent, XTokensRawEvent,
+};
+
+/// `{ "id": account }` or `{ "index": account index }` decoded by the runtime metadata.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+enum AccountRef {
+    Id(AccountId),
+    Index(AccountIndex),
+}
+
+impl AccountRef {
+    fn resolve(self, indices: &BTreeMap<AccountIndex, AccountId>) -> Result<AccountId> {
+        match self {
+            AccountRef::Id(account) => Ok(account),
+            AccountRef::Index(index) => indices
+                .get(&index)
+                .copied()
+                .ok_or_else(|| anyhow!("Unknown account index {}", index)),
+        }
+    }
+}
+
+/// An extrinsic dispatching an `XStaking::claim`, directly or nested in other calls.
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct ClaimExtrinsic {
+    height: BlockNumber,
+    index: u32,
+    call: ClaimCall,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum ClaimCall {
+    /// `XStaking::claim`, the nominator is the signer, or the multisig address if it's the
+    /// proposal of `XMultiSig::execute`.
+    Claim {
+        nominator: AccountRef,
+        intention: AccountRef,
+    },
+    /// `XMultiSig::confirm`, which executes the proposal (maybe a claim) by the last
+    /// confirmation.
+    Confirm {
+        multisig: AccountId,
+        proposal_id: Hash,
+    },
+}
+
+/// The resolved `ClaimCall`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Claimer {
+    Claim {
+        nominator: AccountId,
+        intention: AccountId,
+    },
+    Confirm {
+        multisig: AccountId,
+        proposal_id: Hash,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum RewardEvent {
+    /// `XStaking::Claim` or `XStaking::ClaimV1`, the claim event carries no accounts, the
+    /// nominator and the intention are taken from the `XStaking::claim` extrinsic emitting it.
+    StakingClaim {
+        /// The index of the extrinsic, `None` if it's not emitted by an extrinsic.
+        extrinsic: Option<u32>,
+        dividend: Balance,
+    },
+    /// `XTokens::DepositorClaim` or `XTokens::DepositorClaimV1`
+    DepositorClaim {
+        account: AccountId,
+        token: String,
+        dividend: Balance,
+    },
+    /// `XTokens::DepositorReward`
+    DepositorReward {
+        account: AccountId,
+        token: String,
+        reward: Balance,
+    },
+    /// `XMultisig::ExecMultiSig`, which gives the id of the proposal of an `XMultiSig::execute`.
+    MultisigProposal {
+        extrinsic: Option<u32>,
+        multisig: AccountId,
+        proposal_id: Hash,
+    },
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct Claimed {
+    /// The number of the claims.
+    count: u64,
+    total: Balance,
+    /// The block of the latest claim.
+    last_height: BlockNumber,
+}
+
+impl Claimed {
+    fn add(&mut self, height: BlockNumber, balance: Balance) {
+        self.count += 1;
+        self.total += balance;
+        self.last_height = height;
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct IntentionClaimed {
+    intention: AccountId,
+    #[serde(flatten)]
+    claimed: Claimed,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct AccountRewards {
+    account: AccountId,
+    /// The dividends claimed from the jackpots of the intentions.
+    staking: Vec<IntentionClaimed>,
+    /// The dividends claimed from the jackpots of the tokens.
+    depositor_claims: BTreeMap<String, Claimed>,
+    /// The deposit rewards of the tokens.
+    deposit_rewards: BTreeMap<String, Claimed>,
+    /// The sum of all above.
+    total: Balance,
+}
+
+/// A staking claim whose nominator and intention are unknown.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct UnattributedClaim {
+    height: BlockNumber,
+    /// The index of the extrinsic, `None` if it's not emitted by an extrinsic.
+    extrinsic: Option<u32>,
+    dividend: Balance,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct Rewards {
+    accounts: Vec<AccountRewards>,
+    /// The staking claims that can't be attributed to any account, which are reported instead of
+    /// being dropped.
+    unattributed: Vec<UnattributedClaim>,
+}
+
+/// Export the lifetime rewards claimed by every account up to the target height into
+/// `rewards.json`, by the intentions and the tokens.
+///
+/// The accounts of the staking claims are taken from the `XStaking::claim` extrinsics in the
+/// `extrinsics` archive (also the ones nested in the multisig proposals), with the account indices
+/// resolved by `account-indices.json`. The staking claims that can't be attributed are exported in
+/// the `unattributed` section.
+pub async fn export(conf: &Config) -> Result<()> {
+    let height = conf.height;
+
+    let events = pick_events(conf, reward_event).await?;
+    let claims = pick_extrinsics(conf, claim_extrinsic).await?;
+    let indices: Vec<AccountWithIndex> = load_state(height, "account-indices.json")?;
+    let indices = indices
+        .into_iter()
+        .map(|entry| (entry.index, entry.account))
+        .collect::<BTreeMap<_, _>>();
+
+    let claims = resolve_claims(claims, &indices)?;
+    let rewards = aggregate(events, &claims);
+    log::info!(
+        "Rewarded Account Number: {}, Total Rewards: {}",
+        rewards.accounts.len(),
+        rewards
+            .accounts
+            .iter()
+            .map(|account| account.total)
+            .sum::<Balance>()
+    );
+    if !rewards.unattributed.is_empty() {
+        log::warn!(
+            "{} staking claims can't be attributed, total dividend: {}",
+            rewards.unattributed.len(),
+            rewards
+                .unattributed
+                .iter()
+                .map(|claim| claim.dividend)
+                .sum::<Balance>()
+        );
+    }
+
+    save_state(height, "rewards.json", &rewards)?;
+
+    Ok(())
+}
+
+fn reward_event(record: &ChainXEventRecord) -> Option<RewardEvent> {
+    let text = |bytes: &[u8]| String::from_utf8_lossy(bytes).into_owned();
+    let extrinsic = match record.phase {
+        Phase::ApplyExtrinsic(index) => Some(index),
+        Phase::Finalization => None,
+    };
+    match &record.event {
+        ChainXEvent::XStaking(XStakingRawEvent::Claim(_, _, dividend))
+        | ChainXEvent::XStaking(XStakingRawEvent::ClaimV1(_, _, dividend)) => {
+            Some(RewardEvent::StakingClaim {
+                extrinsic,
+                dividend: *dividend,
+            })
+        }
+        ChainXEvent::XMultisig(XMultisigRawEvent::ExecMultiSig(_, multisig, proposal_id, _)) => {
+            Some(RewardEvent::MultisigProposal {
+                extrinsic,
+                multisig: *multisig,
+                proposal_id: *proposal_id,
+            })
+        }
+        ChainXEvent::XTokens(XTokensRawEvent::DepositorClaim(account, token, _, _, dividend))
+        | ChainXEvent::XTokens(XTokensRawEvent::DepositorClaimV1(account, token, _, _, dividend)) => {
+            Some(RewardEvent::DepositorClaim {
+                account: *account,
+                token: text(token),
+                dividend: *dividend,
+            })
+        }
+        ChainXEvent::XTokens(XTokensRawEvent::DepositorReward(account, token, reward)) => {
+            Some(RewardEvent::DepositorReward {
+                account: *account,
+                token: text(token),
+                reward: *reward,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// The extrinsics dispatching the `XStaking::claim`s, decoded by the runtime metadata.
+fn claim_extrinsic(extrinsic: SignedExtrinsic) -> Option<ClaimExtrinsic> {
+    let claim = serde_json::from_value::<AccountRef>(extrinsic.signer.clone())
+        .and_then(|signer| claim_call(Some(signer), &extrinsic.call));
+    match claim {
+        Ok(call) => call.map(|call| ClaimExtrinsic {
+            height: extrinsic.height,
+            index: extrinsic.index,
+            call,
+        }),
+        Err(err) => {
+            log::warn!(
+                "Block Height {}, extrinsic {}, invalid claim: {}",
+                extrinsic.height,
+                extrinsic.index,
+                err
+            );
+            None
+        }
+    }
+}
+
+/// The claim dispatched by the call with the origin (`None` is the root), the `target` of
+/// `XStaking::claim` is the intention.
+///
+/// The proposal of `XMultiSig::execute` is dispatched by the multisig address, and the one of
+/// `Sudo::sudo` by the root, which can't claim.
+fn claim_call(
+    origin: Option<AccountRef>,
+    call: &Value,
+) -> Result<Option<ClaimCall>, serde_json::Error> {
+    let arg = |name: &str| call["args"][name].clone();
+    match (call["module"].as_str(), call["call"].as_str()) {
+        (Some("XStaking"), Some("claim")) => Ok(match origin {
+            Some(nominator) => Some(ClaimCall::Claim {
+                nominator,
+                intention: serde_json::from_value(arg("target"))?,
+            }),
+            None => None,
+        }),
+        (Some("XMultiSig"), Some("execute")) => {
+            let multisig = serde_json::from_value(arg("multi_sig_addr"))?;
+            claim_call(Some(AccountRef::Id(multisig)), &call["args"]["proposal"])
+        }
+        (Some("XMultiSig"), Some("confirm")) => Ok(Some(ClaimCall::Confirm {
+            multisig: serde_json::from_value(arg("multi_sig_addr"))?,
+            proposal_id: serde_json::from_value(arg("multi_sig_id"))?,
+        })),
+        (Some("Sudo"), Some("sudo")) => claim_call(None, &call["args"]["proposal"]),
+        _ => Ok(None),
+    }
+}
+
+/// The claim extrinsics by the blocks and the extrinsic indices, with the account indices
+/// resolved, fails if any account index is unknown.
+fn resolve_claims(
+    claims: Vec<ClaimExtrinsic>,
+    indices: &BTreeMap<AccountIndex, AccountId>,
+) -> Result<BTreeMap<(BlockNumber, u32), Claimer>> {
+    claims
+        .into_iter()
+        .map(|claim| {
+            let claimer = match claim.call {
+                ClaimCall::Claim {
+                    nominator,
+                    intention,
+                } => Claimer::Claim {
+                    nominator: nominator.resolve(indices)?,
+                    intention: intention.resolve(indices)?,
+                },
+                ClaimCall::Confirm {
+                    multisig,
+                    proposal_id,
+                } => Claimer::Confirm {
+                    multisig,
+                    proposal_id,
+                },
+            };
+            Ok(((claim.height, claim.index), claimer))
+        })
+        .collect()
+}
+
+/// Sum up the rewards of each account by the intentions and the tokens.
+///
+/// A staking claim is attributed by the claim extrinsic emitting it, or by the claim proposed to
+/// the multisig if it's emitted by the `XMultiSig::confirm` executing that proposal. The other
+/// staking claims are unattributed.
+fn aggregate(
+    events: Vec<BlockRecord<RewardEvent>>,
+    claims: &BTreeMap<(BlockNumber, u32), Claimer>,
+) -> Rewards {
+    #[derive(Default)]
+    struct Totals {
+        staking: BTreeMap<AccountId, Claimed>,
+        depositor_claims: BTreeMap<String, Claimed>,
+        deposit_rewards: BTreeMap<String, Claimed>,
+    }
+
+    let mut accounts = BTreeMap::<AccountId, Totals>::new();
+    // The claims proposed to the multisigs by `XMultiSig::execute`, keyed by the proposals.
+    let mut proposals = BTreeMap::<(AccountId, Hash), (AccountId, AccountId)>::new();
+    let mut unattributed = vec![];
+    for BlockRecord { height, record } in events {
+        let claimer =
+            |extrinsic: Option<u32>| extrinsic.and_then(|index| claims.get(&(height, index)));
+        match record {
+            RewardEvent::MultisigProposal {
+                extrinsic,
+                multisig,
+                proposal_id,
+            } => {
+                if let Some(Claimer::Claim {
+                    nominator,
+                    intention,
+                }) = claimer(extrinsic)
+                {
+                    if *nominator == multisig {
+                        proposals.insert((multisig, proposal_id), (*nominator, *intention));
+                    }
+                }
+            }
+            RewardEvent::StakingClaim {
+                extrinsic,
+                dividend,
+            } => {
+                let attributed = match claimer(extrinsic) {
+                    Some(Claimer::Claim {
+                        nominator,
+                        intention,
+                    }) => Some((*nominator, *intention)),
+                    Some(Claimer::Confirm {
+                        multisig,
+                        proposal_id,
+                    }) => proposals.get(&(*multisig, *proposal_id)).copied(),
+                    None => None,
+                };
+                let (nominator, intention) = match attributed {
+                    Some(accounts) => accounts,
+                    None => {
+                        unattributed.push(UnattributedClaim {
+                            height,
+                            extrinsic,
+                            dividend,
+                        });
+                        continue;
+                    }
+                };
+                accounts
+                    .entry(nominator)
+                    .or_default()
+                    .staking
+                    .entry(intention)
+                    .or_default()
+                    .add(height, dividend)
+            }
+            RewardEvent::DepositorClaim {
+                account,
+                token,
+                dividend,
+            } => accounts
+                .entry(account)
+                .or_default()
+                .depositor_claims
+                .entry(token)
+                .or_default()
+                .add(height, dividend),
+            RewardEvent::DepositorReward {
+                account,
+                token,
+                reward,
+            } => accounts
+                .entry(account)
+                .or_default()
+                .deposit_rewards
+                .entry(token)
+                .or_default()
+                .add(height, reward),
+        }
+    }
+
+    let accounts = accounts
+        .into_iter()
+        .map(|(account, rewards)| {
+            let total = rewards
+                .staking
+                .values()
+                .chain(rewards.depositor_claims.values())
+                .chain(rewards.deposit_rewards.values())
+                .map(|claimed| claimed.total)
+                .sum();
+            AccountRewards {
+                account,
+                staking: rewards
+                    .staking
+                    .into_iter()
+                    .map(|(intention, claimed)| IntentionClaimed { intention, claimed })
+                    .collect(),
+                depositor_claims: rewards.depositor_claims,
+                deposit_rewards: rewards.deposit_rewards,
+                total,
+            }
+        })
+        .collect();
+    Rewards {
+        accounts,
+        unattributed,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::chunk::block_records;
+    use serde_json::json;
+
+    #[test]
+    fn test_claim_extrinsic() {
+        let nominator = AccountId::repeat_byte(1);
+        let multisig = AccountId::repeat_byte(2);
+        let extrinsic = |call| -> SignedExtrinsic {
+            serde_json::from_value(json!({
+                "height": 3,
+                "index": 1,
+                "signer": { "id": nominator },
+                "nonce": 0,
+                "acceleration": 1,
+                "call": call,
+                "success": true,
+            }))
+            .unwrap()
+        };
+        let claim = json!({
+            "module": "XStaking",
+            "call": "claim",
+            "args": { "target": { "index": 7 } },
+        });
+        let claim_extrinsic_of = |nominator| ClaimExtrinsic {
+            height: 3,
+            index: 1,
+            call: ClaimCall::Claim {
+                nominator,
+                intention: AccountRef::Index(7),
+            },
+        };
+
+        assert_eq!(
+            claim_extrinsic(extrinsic(claim.clone())),
+            Some(claim_extrinsic_of(AccountRef::Id(nominator)))
+        );
+        assert_eq!(
+            claim_extrinsic(extrinsic(json!({
+                "module": "XStaking",
+                "call": "nominate",
+                "args": { "target": { "index": 7 } },
+            }))),
+            None
+        );
+        // The claim proposed to the multisig is claimed by the multisig.
+        assert_eq!(
+            claim_extrinsic(extrinsic(json!({
+                "module": "XMultiSig",
+                "call": "execute",
+                "args": { "multi_sig_addr": multisig, "proposal": claim.clone() },
+            }))),
+            Some(claim_extrinsic_of(AccountRef::Id(multisig)))
+        );
+        assert_eq!(
+            claim_extrinsic(extrinsic(json!({
+                "module": "XMultiSig",
+                "call": "confirm",
+                "args": { "multi_sig_addr": multisig, "multi_sig_id": Hash::repeat_byte(9) },
+            }))),
+            Some(ClaimExtrinsic {
+                height: 3,
+                index: 1,
+                call: ClaimCall::Confirm {
+                    multisig,
+                    proposal_id: Hash::repeat_byte(9),
+                },
+            })
+        );
+        // The root can't claim.
+        assert_eq!(
+            claim_extrinsic(extrinsic(json!({
+                "module": "Sudo",
+                "call": "sudo",
+                "args": { "proposal": claim },
+            }))),
+            None
+        );
+
+        let indices = vec![(7, AccountId::repeat_byte(0xa))].into_iter().collect();
+        let claim = claim_extrinsic_of(AccountRef::Id(nominator));
+        assert_eq!(
+            resolve_claims(vec![claim.clone()], &indices).unwrap()[&(3, 1)],
+            Claimer::Claim {
+                nominator,
+                intention: AccountId::repeat_byte(0xa),
+            }
+        );
+        assert!(resolve_claims(vec![claim], &BTreeMap::new()).is_err());
+    }
+
+    #[test]
+    fn test_aggregate() {
+        let a = AccountId::repeat_byte(1);
+        let b = AccountId::repeat_byte(2);
+        let multisig = AccountId::repeat_byte(3);
+        let intention = AccountId::repeat_byte(0xa);
+        let proposal_id = Hash::repeat_byte(9);
+        let claim = |nominator| Claimer::Claim {
+            nominator,
+            intention,
+        };
+        let claims = vec![
+            ((1, 1), claim(a)),
+            ((2, 1), claim(a)),
+            ((3, 2), claim(b)),
+            // The claim proposed to the multisig at #7, executed by the confirm at #8.
+            ((7, 1), claim(multisig)),
+            (
+                (8, 2),
+                Claimer::Confirm {
+                    multisig,
+                    proposal_id,
+                },
+            ),
+        ]
+        .into_iter()
+        .collect();
+        let staking_claim = |extrinsic, dividend| RewardEvent::StakingClaim {
+            extrinsic,
+            dividend,
+        };
+        let events = vec![
+            (1, staking_claim(Some(1), 100)),
+            (2, staking_claim(Some(1), 50)),
+            (3, staking_claim(Some(2), 10)),
+            // The claim of block #4 has no claim extrinsic.
+            (4, staking_claim(Some(1), 1_000)),
+            (
+                5,
+                RewardEvent::DepositorClaim {
+                    account: a,
+                    token: "BTC".to_string(),
+                    dividend: 20,
+                },
+            ),
+            (
+                6,
+                RewardEvent::DepositorReward {
+                    account: a,
+                    token: "BTC".to_string(),
+                    reward: 5,
+                },
+            ),
+            (
+                7,
+                RewardEvent::MultisigProposal {
+                    extrinsic: Some(1),
+                    multisig,
+                    proposal_id,
+                },
+            ),
+            (8, staking_claim(Some(2), 30)),
+            (9, staking_claim(None, 3)),
+        ];
+
+        let rewards = aggregate(block_records(events), &claims);
+        let accounts = rewards.accounts;
+        assert_eq!(accounts.len(), 3);
+        assert_eq!(accounts[0].account, a);
+        assert_eq!(accounts[0].total, 175);
+        assert_eq!(
+            accounts[0].staking,
+            vec![IntentionClaimed {
+                intention,
+                claimed: Claimed {
+                    count: 2,
+                    total: 150,
+                    last_height: 2,
+                },
+            }]
+        );
+        assert_eq!(accounts[0].depositor_claims["BTC"].total, 20);
+        assert_eq!(accounts[0].deposit_rewards["BTC"].last_height, 6);
+        assert_eq!(accounts[1].account, b);
+        assert_eq!(accounts[1].total, 10);
+        assert_eq!(accounts[2].account, multisig);
+        assert_eq!(accounts[2].staking[0].claimed.last_height, 8);
+        assert_eq!(accounts[2].total, 30);
+
+        assert_eq!(
+            rewards.unattributed,
+            vec![
+                UnattributedClaim {
+                    height: 4,
+                    extrinsic: Some(1),
+                    dividend: 1_000,
+                },
+                UnattributedClaim {
+                    height: 9,
+                    extrinsic: None,
+                    dividend: 3,
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,171 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+use anyhow::{anyhow, ensure, Result};
+use serde::{Deserialize, Serialize};
+
+use super::{chunk::BlockRecord, events::pick_events};
+use crate::{
+    load_state, save_state, AccountId, Balance, BlockNumber, ChainXEvent, ChainXEventRecord,
+    Config, TotalAssetInfo, XSdotRawEvent,
+};
+
+/// `XSdot::Claimed`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct Claim {
+    account: AccountId,
+    /// The hex ethereum address.
+    ethereum_address: String,
+    balance: Balance,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct ClaimEntry {
+    height: BlockNumber,
+    #[serde(flatten)]
+    claim: Claim,
+}
+
+/// The SDOT issued but not claimed, which is attributed to the legacy SDOT account.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct Unclaimed {
+    account: AccountId,
+    balance: Balance,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct SdotClaims {
+    /// The sum of all claims.
+    total_claimed: Balance,
+    /// The total `SDOT` of `assets-total.json`.
+    total_issued: Balance,
+    unclaimed: Unclaimed,
+    claims: Vec<ClaimEntry>,
+}
+
+/// 5T5oFEBXxgjkjtUKM926ZPJzNVf4w8baTgEa1JKLA1bD9J6D, the `legacySDOTAccount` of the genesis
+/// params builder.
+const LEGACY_SDOT_ACCOUNT: &str =
+    "985ce3564a5e74bff91a742388cbb392fd98994b22109fef6efe8d0792662d30";
+
+fn legacy_sdot_account() -> AccountId {
+    AccountId::from_slice(&hex::decode(LEGACY_SDOT_ACCOUNT).expect("valid hex; qed"))
+}
+
+/// Export all SDOT claims up to the target height into `sdot-claims.json`, and reconcile the
+/// claimed total against the total `SDOT`, the unclaimed SDOT is attributed to the legacy SDOT
+/// account.
+pub async fn export(conf: &Config) -> Result<()> {
+    let height = conf.height;
+
+    let claims = pick_events(conf, claim).await?;
+    let total_issued = load_state::<_, Vec<TotalAssetInfo>>(height, "assets-total.json")?
+        .into_iter()
+        .find(|asset| asset.name == "SDOT")
+        .ok_or_else(|| anyhow!("No SDOT in assets-total.json"))?
+        .details
+        .values()
+        .sum();
+
+    let value = reconcile(claims, total_issued, legacy_sdot_account())?;
+    log::info!(
+        "SDOT Claim Number: {}, Total Claimed: {}, Total Issued: {}, Unclaimed: {} (Account {:?})",
+        value.claims.len(),
+        value.total_claimed,
+        value.total_issued,
+        value.unclaimed.balance,
+        value.unclaimed.account
+    );
+
+    save_state(height, "sdot-claims.json", &value)?;
+
+    Ok(())
+}
+
+fn claim(record: &ChainXEventRecord) -> Option<Claim> {
+    match &record.event {
+        ChainXEvent::XSdot(XSdotRawEvent::Claimed(account, ethereum_address, balance)) => {
+            Some(Claim {
+                account: *account,
+                ethereum_address: format!("0x{}", hex::encode(ethereum_address)),
+                balance: *balance,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Sum up the claims in order against the total `SDOT` issued, the difference is attributed to
+/// the `legacy` account.
+///
+/// Fails if more SDOT is claimed than issued.
+fn reconcile(
+    claims: Vec<BlockRecord<Claim>>,
+    total_issued: Balance,
+    legacy: AccountId,
+) -> Result<SdotClaims> {
+    let claims = claims
+        .into_iter()
+        .map(|BlockRecord { height, record }| ClaimEntry {
+            height,
+            claim: record,
+        })
+        .collect::<Vec<_>>();
+    let total_claimed = claims.iter().map(|entry| entry.claim.balance).sum();
+    ensure!(
+        total_claimed <= total_issued,
+        "The SDOT claimed ({}) exceeds the SDOT issued ({})",
+        total_claimed,
+        total_issued
+    );
+    Ok(SdotClaims {
+        total_claimed,
+        total_issued,
+        unclaimed: Unclaimed {
+            account: legacy,
+            balance: total_issued - total_claimed,
+        },
+        claims,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::chunk::block_records;
+
+    fn claim(byte: u8, balance: Balance) -> Claim {
+        Claim {
+            account: AccountId::repeat_byte(byte),
+            ethereum_address: format!("0x{}", hex::encode([byte; 20])),
+            balance,
+        }
+    }
+
+    #[test]
+    fn test_reconcile() {
+        let claims = vec![(5, claim(2, 300)), (9, claim(1, 200))];
+        let legacy = legacy_sdot_account();
+        let reconcile = |claims: Vec<(BlockNumber, Claim)>, total_issued| {
+            reconcile(block_records(claims), total_issued, legacy)
+        };
+
+        let value = reconcile(claims.clone(), 600).unwrap();
+        assert_eq!(value.total_claimed, 500);
+        assert_eq!(
+            value.unclaimed,
+            Unclaimed {
+                account: legacy,
+                balance: 100,
+            }
+        );
+        assert_eq!(value.claims.len(), 2);
+        assert_eq!(value.claims[0].height, 5);
+        assert_eq!(value.claims[1].claim, claim(1, 200));
+
+        assert_eq!(reconcile(claims.clone(), 500).unwrap().unclaimed.balance, 0);
+        assert!(reconcile(claims, 400).is_err());
+        let value = reconcile(vec![], 700).unwrap();
+        assert_eq!(value.total_claimed, 0);
+        assert_eq!(value.unclaimed.balance, 700);
+    }
+}
@@ -1,22 +1,20 @@
 // Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
 
-use chainx_state_exporter::*;
-use serde::Serialize;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize)]
-struct SessionIndexWithHeight {
-    height: BlockNumber,
-    session_index: BlockNumber,
-}
+use crate::{save_state, BlockNumber, ChainX, Config};
 
-#[async_std::main]
-async fn main() -> anyhow::Result<()> {
-    env_logger::init();
+#[derive(Debug, Serialize, Deserialize)]
+pub(super) struct SessionIndexWithHeight {
+    pub height: BlockNumber,
+    pub session_index: BlockNumber,
+}
 
-    let conf = CmdConfig::init()?;
+pub async fn export(conf: &Config) -> Result<()> {
     let height = conf.height;
 
-    let chainx = ChainX::new(&conf.chainx_ws_url).await?;
+    let chainx = ChainX::connect(conf).await?;
     let hash = chainx.block_hash(Some(height)).await?;
     log::info!("Block Height {}, Hash: {:?}", height, hash);
 
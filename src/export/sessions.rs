@@ -0,0 +1,220 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::session_index::SessionIndexWithHeight;
+use super::{chunk::BlockRecord, events::pick_events};
+use crate::{
+    load_state, save_state, AccountId, Balance, BlockNumber, ChainXEvent, ChainXEventRecord,
+    Config, XSessionRawEvent, XStakingRawEvent,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+enum SessionEvent {
+    /// `XSession::NewSession`
+    NewSession { session: BlockNumber },
+    /// `XStaking::Rotation`
+    Rotation { validators: Vec<(AccountId, u64)> },
+    /// `XStaking::SessionReward`
+    SessionReward {
+        reward: (Balance, Balance, Balance, Balance),
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct Validator {
+    account: AccountId,
+    weight: u64,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct ValidatorSet {
+    /// The block of the `Rotation`.
+    height: BlockNumber,
+    validators: Vec<Validator>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct Session {
+    session: BlockNumber,
+    /// The block of the `NewSession`.
+    height: BlockNumber,
+    /// The index in `validator_sets` of the validators of the session, `None` before the first
+    /// `Rotation`.
+    validator_set: Option<usize>,
+    /// The balances of every `SessionReward` during the session, as in the event.
+    rewards: Vec<(Balance, Balance, Balance, Balance)>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct SessionTimeline {
+    sessions: Vec<Session>,
+    validator_sets: Vec<ValidatorSet>,
+}
+
+/// Export the timeline of all sessions up to the target height into `sessions.json`, and check
+/// the last session against `session-index.json`.
+pub async fn export(conf: &Config) -> Result<()> {
+    let height = conf.height;
+
+    let events = pick_events(conf, session_event).await?;
+    let timeline = replay(events);
+    log::info!(
+        "Session Number: {}, Validator Set Number: {}",
+        timeline.sessions.len(),
+        timeline.validator_sets.len()
+    );
+
+    let current: SessionIndexWithHeight = load_state(height, "session-index.json")?;
+    let last = timeline.sessions.last().map(|session| session.session);
+    if last != Some(current.session_index) {
+        log::warn!(
+            "The last session {:?} differs from the session index {} at Block #{}",
+            last,
+            current.session_index,
+            current.height
+        );
+    }
+
+    save_state(height, "sessions.json", &timeline)?;
+
+    Ok(())
+}
+
+fn session_event(record: &ChainXEventRecord) -> Option<SessionEvent> {
+    match &record.event {
+        ChainXEvent::XSession(XSessionRawEvent::NewSession(session)) => {
+            Some(SessionEvent::NewSession { session: *session })
+        }
+        ChainXEvent::XStaking(XStakingRawEvent::Rotation(validators)) => {
+            Some(SessionEvent::Rotation {
+                validators: validators.clone(),
+            })
+        }
+        ChainXEvent::XStaking(XStakingRawEvent::SessionReward(a, b, c, d)) => {
+            Some(SessionEvent::SessionReward {
+                reward: (*a, *b, *c, *d),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Replay the events in order, a `Rotation` takes effect from the session it's emitted in, and
+/// a `SessionReward` is counted in the session it's emitted in.
+fn replay(events: Vec<BlockRecord<SessionEvent>>) -> SessionTimeline {
+    let mut timeline = SessionTimeline::default();
+    for BlockRecord { height, record } in events {
+        match record {
+            SessionEvent::NewSession { session } => {
+                let validator_set = timeline.validator_sets.len().checked_sub(1);
+                timeline.sessions.push(Session {
+                    session,
+                    height,
+                    validator_set,
+                    rewards: vec![],
+                });
+            }
+            SessionEvent::Rotation { validators } => {
+                timeline.validator_sets.push(ValidatorSet {
+                    height,
+                    validators: validators
+                        .into_iter()
+                        .map(|(account, weight)| Validator { account, weight })
+                        .collect(),
+                });
+                if let Some(session) = timeline.sessions.last_mut() {
+                    session.validator_set = Some(timeline.validator_sets.len() - 1);
+                }
+            }
+            SessionEvent::SessionReward { reward } => match timeline.sessions.last_mut() {
+                Some(session) => session.rewards.push(reward),
+                None => log::warn!("SessionReward before any session at Block #{}", height),
+            },
+        }
+    }
+    timeline
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{chunk::block_records, events::fixture_events};
+
+    #[test]
+    fn test_replay() {
+        let rotation = |weight| SessionEvent::Rotation {
+            validators: vec![(AccountId::repeat_byte(1), weight)],
+        };
+        let events = vec![
+            (0, rotation(10)),
+            (0, SessionEvent::NewSession { session: 0 }),
+            (
+                150,
+                SessionEvent::SessionReward {
+                    reward: (4, 3, 1, 0),
+                },
+            ),
+            (150, SessionEvent::NewSession { session: 1 }),
+            (300, SessionEvent::NewSession { session: 2 }),
+            (300, rotation(20)),
+            (450, SessionEvent::NewSession { session: 3 }),
+        ];
+        let timeline = replay(block_records(events));
+        assert_eq!(timeline.validator_sets.len(), 2);
+        assert_eq!(timeline.validator_sets[1].validators[0].weight, 20);
+        let sessions = timeline
+            .sessions
+            .iter()
+            .map(|s| (s.session, s.height, s.validator_set, s.rewards.len()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sessions,
+            vec![
+                (0, 0, Some(0), 1),
+                (1, 150, Some(0), 0),
+                (2, 300, Some(1), 0),
+                (3, 450, Some(1), 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fixture() {
+        let timeline = replay(fixture_events("sessions", session_event));
+        assert_eq!(
+            timeline.validator_sets,
+            vec![ValidatorSet {
+                height: 1200,
+                validators: vec![
+                    Validator {
+                        account: AccountId::repeat_byte(1),
+                        weight: 10,
+                    },
+                    Validator {
+                        account: AccountId::repeat_byte(2),
+                        weight: 20,
+                    },
+                ],
+            }]
+        );
+        assert_eq!(
+            timeline.sessions,
+            vec![
+                Session {
+                    session: 7,
+                    height: 1200,
+                    validator_set: Some(0),
+                    rewards: vec![(400, 300, 100, 0)],
+                },
+                Session {
+                    session: 8,
+                    height: 1350,
+                    validator_set: Some(0),
+                    rewards: vec![],
+                },
+            ]
+        );
+    }
+}
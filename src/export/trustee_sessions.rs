@@ -0,0 +1,252 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::{chunk::BlockRecord, events::pick_events};
+use crate::{
+    save_state, AccountId, BlockNumber, BtcTrusteeAddrInfo, ChainXEvent, ChainXEventRecord, Config,
+    XBridgeFeaturesRawEvent,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+enum TrusteeEvent {
+    /// `XBridgeFeatures::SetBitcoinTrusteeProps`
+    Props {
+        account: AccountId,
+        props: TrusteeProps,
+    },
+    /// `XBridgeFeatures::BitcoinNewTrustees`
+    NewTrustees {
+        session_number: u32,
+        trustees: Vec<AccountId>,
+        hot_address: TrusteeAddress,
+        cold_address: TrusteeAddress,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct TrusteeProps {
+    about: String,
+    /// The hex public keys.
+    hot_entity: String,
+    cold_entity: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct TrusteeAddress {
+    /// The base58 bitcoin address.
+    addr: String,
+    redeem_script: String,
+}
+
+impl From<&BtcTrusteeAddrInfo> for TrusteeAddress {
+    fn from(info: &BtcTrusteeAddrInfo) -> Self {
+        Self {
+            addr: info.addr.to_string(),
+            redeem_script: format!("0x{}", hex::encode(&info.redeem_script)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct Trustee {
+    account: AccountId,
+    /// The props of the trustee when the session began, `None` if never set.
+    props: Option<TrusteeProps>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct TrusteeSession {
+    session_number: u32,
+    /// The block of the `BitcoinNewTrustees`.
+    height: BlockNumber,
+    trustees: Vec<Trustee>,
+    hot_address: TrusteeAddress,
+    cold_address: TrusteeAddress,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct TrusteeSessions {
+    /// The latest session, which is the current trustee set.
+    current: Option<TrusteeSession>,
+    /// All sessions in order, including the current one.
+    sessions: Vec<TrusteeSession>,
+    /// The latest props of all trustees, including the ones not in any session.
+    props: BTreeMap<AccountId, TrusteeProps>,
+}
+
+/// Export all bitcoin trustee sessions up to the target height into `trustee-sessions.json`.
+pub async fn export(conf: &Config) -> Result<()> {
+    let height = conf.height;
+
+    let events = pick_events(conf, trustee_event).await?;
+    let sessions = replay(events);
+    log::info!(
+        "Trustee Session Number: {}, Current Session: {:?}",
+        sessions.sessions.len(),
+        sessions
+            .current
+            .as_ref()
+            .map(|session| session.session_number)
+    );
+
+    save_state(height, "trustee-sessions.json", &sessions)?;
+
+    Ok(())
+}
+
+fn trustee_event(record: &ChainXEventRecord) -> Option<TrusteeEvent> {
+    match &record.event {
+        ChainXEvent::XBridgeFeatures(XBridgeFeaturesRawEvent::SetBitcoinTrusteeProps(
+            account,
+            props,
+        )) => Some(TrusteeEvent::Props {
+            account: *account,
+            props: TrusteeProps {
+                about: String::from_utf8_lossy(&props.about).into_owned(),
+                hot_entity: format!("0x{}", hex::encode(&*props.hot_entity)),
+                cold_entity: format!("0x{}", hex::encode(&*props.cold_entity)),
+            },
+        }),
+        ChainXEvent::XBridgeFeatures(XBridgeFeaturesRawEvent::BitcoinNewTrustees(
+            session_number,
+            info,
+        )) => Some(TrusteeEvent::NewTrustees {
+            session_number: *session_number,
+            trustees: info.trustee_list.clone(),
+            hot_address: (&info.hot_address).into(),
+            cold_address: (&info.cold_address).into(),
+        }),
+        _ => None,
+    }
+}
+
+/// Replay the events in order, each session takes the props of its trustees at that time.
+fn replay(events: Vec<BlockRecord<TrusteeEvent>>) -> TrusteeSessions {
+    let mut props = BTreeMap::new();
+    let mut sessions = vec![];
+    for BlockRecord { height, record } in events {
+        match record {
+            TrusteeEvent::Props {
+                account,
+                props: account_props,
+            } => {
+                props.insert(account, account_props);
+            }
+            TrusteeEvent::NewTrustees {
+                session_number,
+                trustees,
+                hot_address,
+                cold_address,
+            } => sessions.push(TrusteeSession {
+                session_number,
+                height,
+                trustees: trustees
+                    .into_iter()
+                    .map(|account| Trustee {
+                        account,
+                        props: props.get(&account).cloned(),
+                    })
+                    .collect(),
+                hot_address,
+                cold_address,
+            }),
+        }
+    }
+    TrusteeSessions {
+        current: sessions.last().cloned(),
+        sessions,
+        props,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{chunk::block_records, events::fixture_events};
+
+    fn props(about: &str) -> TrusteeProps {
+        TrusteeProps {
+            about: about.to_string(),
+            hot_entity: "0x02".to_string(),
+            cold_entity: "0x03".to_string(),
+        }
+    }
+
+    fn new_trustees(session_number: u32, trustees: &[u8]) -> TrusteeEvent {
+        let address = TrusteeAddress {
+            addr: "3Cg16oUPFUx1cLBUSUHLq3iLgdGhCGpFKy".to_string(),
+            redeem_script: "0x52ae".to_string(),
+        };
+        TrusteeEvent::NewTrustees {
+            session_number,
+            trustees: trustees
+                .iter()
+                .map(|byte| AccountId::repeat_byte(*byte))
+                .collect(),
+            hot_address: address.clone(),
+            cold_address: address,
+        }
+    }
+
+    #[test]
+    fn test_replay() {
+        let props_event = |byte, about| TrusteeEvent::Props {
+            account: AccountId::repeat_byte(byte),
+            props: props(about),
+        };
+        let events = vec![
+            (1, props_event(1, "a")),
+            (1, props_event(2, "b")),
+            (2, new_trustees(0, &[1, 2])),
+            (3, props_event(1, "c")),
+            (3, props_event(3, "d")),
+            (4, new_trustees(1, &[1, 3, 4])),
+        ];
+        let sessions = replay(block_records(events));
+        assert_eq!(sessions.sessions.len(), 2);
+        assert_eq!(sessions.sessions[0].height, 2);
+        assert_eq!(sessions.sessions[0].trustees[0].props, Some(props("a")));
+        let current = sessions.current.unwrap();
+        assert_eq!(current.session_number, 1);
+        assert_eq!(current.trustees[0].props, Some(props("c")));
+        assert_eq!(current.trustees[2].props, None);
+        assert_eq!(sessions.props.len(), 3);
+    }
+
+    #[test]
+    fn test_fixture() {
+        let sessions = replay(fixture_events("trustee_sessions", trustee_event));
+        assert_eq!(sessions.sessions.len(), 1);
+        let current = sessions.current.unwrap();
+        assert_eq!(current.session_number, 0);
+        assert_eq!(current.height, 400);
+        assert_eq!(
+            current.trustees[0],
+            Trustee {
+                account: AccountId::repeat_byte(1),
+                props: Some(TrusteeProps {
+                    about: "trustee 1".to_string(),
+                    hot_entity: format!("0x02{}", hex::encode([0x31; 32])),
+                    cold_entity: format!("0x03{}", hex::encode([0x41; 32])),
+                }),
+            }
+        );
+        assert_eq!(current.trustees[1].account, AccountId::repeat_byte(2));
+        assert_eq!(current.trustees[1].props, None);
+        assert_eq!(
+            current.hot_address,
+            TrusteeAddress {
+                addr: "396z58ZZuVM6UjvvL9crrFnjiGha1crBZG".to_string(),
+                redeem_script: "0x52ae".to_string(),
+            }
+        );
+        assert_eq!(
+            current.cold_address.addr,
+            "3AZv8G1SmPBQgMrRXqatTumudHQ5nPqzs5"
+        );
+    }
+}
@@ -0,0 +1,271 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::{chunk::BlockRecord, events::pick_events};
+use crate::{
+    load_state, save_state, AccountId, Balance, BlockNumber, ChainXEvent, ChainXEventRecord,
+    Config, IntentionInfoV1, XFisherRawEvent, XSessionRawEvent, XStakingRawEvent,
+};
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+enum OffenceEvent {
+    /// `XSession::NewSession`, to know the session of the missed blocks.
+    NewSession { session: BlockNumber },
+    /// `XFisher::SlashDoubleSigner`
+    Slash {
+        /// The two block numbers of the double signing.
+        heights: (BlockNumber, BlockNumber),
+        round: u64,
+        validator: AccountId,
+        slashed: Balance,
+    },
+    /// `XStaking::MissedBlocksOfOfflineValidatorPerSession`
+    MissedBlocks { validators: Vec<(AccountId, u32)> },
+    /// `XStaking::EnforceValidatorsInactive`
+    Inactivate { validators: Vec<AccountId> },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct Slash {
+    /// The block of the `SlashDoubleSigner`.
+    height: BlockNumber,
+    heights: (BlockNumber, BlockNumber),
+    round: u64,
+    slashed: Balance,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct MissedBlocks {
+    /// The index of the latest `NewSession` up to the event, `None` if there's none.
+    session: Option<BlockNumber>,
+    height: BlockNumber,
+    missed: u32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+struct ValidatorOffences {
+    account: AccountId,
+    /// The name in `intentions.json`, empty if it's not an intention at the height.
+    name: String,
+    is_validator: bool,
+    is_active: bool,
+    slashes: Vec<Slash>,
+    total_slashed: Balance,
+    missed_blocks: Vec<MissedBlocks>,
+    total_missed: u64,
+    /// The blocks where the validator is enforced to be inactive.
+    inactivations: Vec<BlockNumber>,
+}
+
+/// Export the slashes, the missed blocks of each session and the forced inactivations of every
+/// intention up to the target height into `validator-offences.json`.
+pub async fn export(conf: &Config) -> Result<()> {
+    let height = conf.height;
+
+    let events = pick_events(conf, offence_event).await?;
+    let mut offences = replay(events);
+
+    let intentions: Vec<IntentionInfoV1> = load_state(height, "intentions.json")?;
+    for intention in intentions {
+        let common = intention.intention_common;
+        let offence = entry(&mut offences, common.account);
+        offence.name = common.name;
+        offence.is_validator = common.is_validator;
+        offence.is_active = common.is_active;
+    }
+    for offence in offences.values().filter(|offence| offence.name.is_empty()) {
+        log::warn!(
+            "Offences of {:?}, which is not an intention",
+            offence.account
+        );
+    }
+
+    let offences = offences
+        .into_iter()
+        .map(|(_, offence)| offence)
+        .collect::<Vec<_>>();
+    log::info!(
+        "Validator Number: {}, Slashed: {}, Missed Blocks: {}, Inactivated: {}",
+        offences.len(),
+        offences.iter().filter(|o| !o.slashes.is_empty()).count(),
+        offences.iter().filter(|o| o.total_missed > 0).count(),
+        offences
+            .iter()
+            .filter(|o| !o.inactivations.is_empty())
+            .count(),
+    );
+
+    save_state(height, "validator-offences.json", &offences)?;
+
+    Ok(())
+}
+
+fn offence_event(record: &ChainXEventRecord) -> Option<OffenceEvent> {
+    match &record.event {
+        ChainXEvent::XSession(XSessionRawEvent::NewSession(session)) => {
+            Some(OffenceEvent::NewSession { session: *session })
+        }
+        ChainXEvent::XFisher(XFisherRawEvent::SlashDoubleSigner(
+            first,
+            second,
+            round,
+            validator,
+            slashed,
+        )) => Some(OffenceEvent::Slash {
+            heights: (*first, *second),
+            round: *round,
+            validator: *validator,
+            slashed: *slashed,
+        }),
+        ChainXEvent::XStaking(XStakingRawEvent::MissedBlocksOfOfflineValidatorPerSession(
+            validators,
+        )) => Some(OffenceEvent::MissedBlocks {
+            validators: validators.clone(),
+        }),
+        ChainXEvent::XStaking(XStakingRawEvent::EnforceValidatorsInactive(validators)) => {
+            Some(OffenceEvent::Inactivate {
+                validators: validators.clone(),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Replay the events in order into the offences of each validator.
+fn replay(events: Vec<BlockRecord<OffenceEvent>>) -> BTreeMap<AccountId, ValidatorOffences> {
+    let mut offences = BTreeMap::new();
+    let mut session = None;
+    for BlockRecord { height, record } in events {
+        match record {
+            OffenceEvent::NewSession { session: index } => session = Some(index),
+            OffenceEvent::Slash {
+                heights,
+                round,
+                validator,
+                slashed,
+            } => {
+                let offence = entry(&mut offences, validator);
+                offence.total_slashed += slashed;
+                offence.slashes.push(Slash {
+                    height,
+                    heights,
+                    round,
+                    slashed,
+                });
+            }
+            OffenceEvent::MissedBlocks { validators } => {
+                for (validator, missed) in validators {
+                    let offence = entry(&mut offences, validator);
+                    offence.total_missed += u64::from(missed);
+                    offence.missed_blocks.push(MissedBlocks {
+                        session,
+                        height,
+                        missed,
+                    });
+                }
+            }
+            OffenceEvent::Inactivate { validators } => {
+                for validator in validators {
+                    entry(&mut offences, validator).inactivations.push(height);
+                }
+            }
+        }
+    }
+    offences
+}
+
+fn entry(
+    offences: &mut BTreeMap<AccountId, ValidatorOffences>,
+    account: AccountId,
+) -> &mut ValidatorOffences {
+    offences
+        .entry(account)
+        .or_insert_with(|| ValidatorOffences {
+            account,
+            ..Default::default()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::{chunk::block_records, events::fixture_events};
+
+    #[test]
+    fn test_replay() {
+        let a = AccountId::repeat_byte(1);
+        let b = AccountId::repeat_byte(2);
+        let events = vec![
+            (
+                1,
+                OffenceEvent::MissedBlocks {
+                    validators: vec![(a, 1)],
+                },
+            ),
+            (150, OffenceEvent::NewSession { session: 1 }),
+            (
+                150,
+                OffenceEvent::MissedBlocks {
+                    validators: vec![(a, 3), (b, 10)],
+                },
+            ),
+            (
+                150,
+                OffenceEvent::Inactivate {
+                    validators: vec![b],
+                },
+            ),
+            (
+                200,
+                OffenceEvent::Slash {
+                    heights: (180, 181),
+                    round: 2,
+                    validator: a,
+                    slashed: 1_000,
+                },
+            ),
+        ];
+        let offences = replay(block_records(events));
+        assert_eq!(offences.len(), 2);
+        assert_eq!(offences[&a].total_missed, 4);
+        assert_eq!(offences[&a].missed_blocks[0].session, None);
+        assert_eq!(offences[&a].missed_blocks[1].session, Some(1));
+        assert_eq!(offences[&a].total_slashed, 1_000);
+        assert_eq!(offences[&a].slashes[0].height, 200);
+        assert!(offences[&a].inactivations.is_empty());
+        assert_eq!(offences[&b].total_missed, 10);
+        assert_eq!(offences[&b].inactivations, vec![150]);
+    }
+
+    #[test]
+    fn test_fixture() {
+        let a = AccountId::repeat_byte(1);
+        let b = AccountId::repeat_byte(2);
+        let offences = replay(fixture_events("validator_offences", offence_event));
+        assert_eq!(offences.len(), 2);
+        assert_eq!(
+            offences[&a].missed_blocks,
+            vec![MissedBlocks {
+                session: Some(5),
+                height: 1000,
+                missed: 3,
+            }]
+        );
+        assert_eq!(
+            offences[&a].slashes,
+            vec![Slash {
+                height: 1100,
+                heights: (1050, 1051),
+                round: 2,
+                slashed: 1_000,
+            }]
+        );
+        assert_eq!(offences[&a].total_slashed, 1_000);
+        assert_eq!(offences[&b].total_missed, 10);
+        assert_eq!(offences[&b].inactivations, vec![1000]);
+    }
+}
@@ -0,0 +1,282 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::{chunk::BlockRecord, events::pick_events};
+use crate::{
+    save_state, AccountId, ApplicationState, Balance, BlockNumber, Chain, ChainXEvent,
+    ChainXEventRecord, Config, TxState, XBitcoinRawEvent, XRecordsRawEvent,
+};
+
+/// The events of the withdrawal applications.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+enum WithdrawalEvent {
+    /// `XRecords::WithdrawalApply`
+    Apply {
+        id: u32,
+        account: AccountId,
+        chain: Chain,
+        token: String,
+        balance: Balance,
+        memo: String,
+        addr: String,
+    },
+    /// `XRecords::WithdrawalFinish`
+    Finish { id: u32, state: ApplicationState },
+    /// `XBitcoin::CreateWithdrawalProposal`, the applications are being processed by the trustees.
+    Proposal { ids: Vec<u32> },
+    /// `XBitcoin::DropWithdrawalProposal`, the applications are applying again.
+    DropProposal { ids: Vec<u32> },
+    /// `XBitcoin::Withdrawal`, the bitcoin transaction of the application.
+    BitcoinTx {
+        id: u32,
+        tx_hash: String,
+        tx_state: TxState,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(super) struct Withdrawal {
+    id: u32,
+    /// The block of the application.
+    height: BlockNumber,
+    pub account: AccountId,
+    chain: Chain,
+    pub token: String,
+    pub balance: Balance,
+    memo: String,
+    addr: String,
+    pub state: ApplicationState,
+    /// The block of the `WithdrawalFinish`.
+    finish_height: Option<BlockNumber>,
+    tx_hash: Option<String>,
+    tx_state: Option<TxState>,
+    /// Still `Applying` or `Processing`, the balance is reserved and should be refunded.
+    pending: bool,
+}
+
+/// Export all withdrawal applications up to the target height into `withdrawals.json`, the
+/// pending ones are reported.
+pub async fn export(conf: &Config) -> Result<()> {
+    let height = conf.height;
+
+    let events = pick_events(conf, withdrawal_event).await?;
+    let withdrawals = rebuild(events);
+
+    let pending = withdrawals
+        .iter()
+        .filter(|withdrawal| withdrawal.pending)
+        .collect::<Vec<_>>();
+    for withdrawal in &pending {
+        log::warn!(
+            "Withdrawal #{} of {:?} is still {:?}: {} {}",
+            withdrawal.id,
+            withdrawal.account,
+            withdrawal.state,
+            withdrawal.balance,
+            withdrawal.token
+        );
+    }
+    log::info!(
+        "Withdrawal Number: {}, Pending Withdrawal Number: {}",
+        withdrawals.len(),
+        pending.len()
+    );
+
+    save_state(height, "withdrawals.json", &withdrawals)?;
+
+    Ok(())
+}
+
+fn withdrawal_event(record: &ChainXEventRecord) -> Option<WithdrawalEvent> {
+    let text = |bytes: &[u8]| String::from_utf8_lossy(bytes).into_owned();
+    match &record.event {
+        ChainXEvent::XRecords(XRecordsRawEvent::WithdrawalApply(
+            id,
+            account,
+            chain,
+            token,
+            balance,
+            memo,
+            addr,
+        )) => Some(WithdrawalEvent::Apply {
+            id: *id,
+            account: *account,
+            chain: chain.clone(),
+            token: text(token),
+            balance: *balance,
+            memo: text(memo),
+            addr: text(addr),
+        }),
+        ChainXEvent::XRecords(XRecordsRawEvent::WithdrawalFinish(id, state)) => {
+            Some(WithdrawalEvent::Finish {
+                id: *id,
+                state: *state,
+            })
+        }
+        ChainXEvent::XBitcoin(XBitcoinRawEvent::CreateWithdrawalProposal(_, ids)) => {
+            Some(WithdrawalEvent::Proposal { ids: ids.clone() })
+        }
+        ChainXEvent::XBitcoin(XBitcoinRawEvent::DropWithdrawalProposal(_, _, ids)) => {
+            Some(WithdrawalEvent::DropProposal { ids: ids.clone() })
+        }
+        ChainXEvent::XBitcoin(XBitcoinRawEvent::Withdrawal(id, tx_hash, tx_state)) => {
+            Some(WithdrawalEvent::BitcoinTx {
+                id: *id,
+                tx_hash: format!("0x{}", hex::encode(tx_hash)),
+                tx_state: *tx_state,
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Apply the events in order, the events of unknown applications are ignored.
+fn rebuild(events: Vec<BlockRecord<WithdrawalEvent>>) -> Vec<Withdrawal> {
+    let mut withdrawals = BTreeMap::new();
+    for BlockRecord { height, record } in events {
+        match record {
+            WithdrawalEvent::Apply {
+                id,
+                account,
+                chain,
+                token,
+                balance,
+                memo,
+                addr,
+            } => {
+                withdrawals.insert(
+                    id,
+                    Withdrawal {
+                        id,
+                        height,
+                        account,
+                        chain,
+                        token,
+                        balance,
+                        memo,
+                        addr,
+                        state: ApplicationState::Applying,
+                        finish_height: None,
+                        tx_hash: None,
+                        tx_state: None,
+                        pending: true,
+                    },
+                );
+            }
+            WithdrawalEvent::Finish { id, state } => {
+                if let Some(withdrawal) = withdrawals.get_mut(&id) {
+                    withdrawal.state = state;
+                    withdrawal.finish_height = Some(height);
+                }
+            }
+            WithdrawalEvent::Proposal { ids } => {
+                for id in ids {
+                    if let Some(withdrawal) = withdrawals.get_mut(&id) {
+                        if withdrawal.state == ApplicationState::Applying {
+                            withdrawal.state = ApplicationState::Processing;
+                        }
+                    }
+                }
+            }
+            WithdrawalEvent::DropProposal { ids } => {
+                for id in ids {
+                    if let Some(withdrawal) = withdrawals.get_mut(&id) {
+                        if withdrawal.state == ApplicationState::Processing {
+                            withdrawal.state = ApplicationState::Applying;
+                        }
+                    }
+                }
+            }
+            WithdrawalEvent::BitcoinTx {
+                id,
+                tx_hash,
+                tx_state,
+            } => {
+                if let Some(withdrawal) = withdrawals.get_mut(&id) {
+                    withdrawal.tx_hash = Some(tx_hash);
+                    withdrawal.tx_state = Some(tx_state);
+                }
+            }
+        }
+    }
+
+    withdrawals
+        .into_iter()
+        .map(|(_, mut withdrawal)| {
+            withdrawal.pending = matches!(
+                withdrawal.state,
+                ApplicationState::Applying | ApplicationState::Processing
+            );
+            withdrawal
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::chunk::block_records;
+
+    fn apply(id: u32) -> WithdrawalEvent {
+        WithdrawalEvent::Apply {
+            id,
+            account: AccountId::repeat_byte(id as u8),
+            chain: Chain::Bitcoin,
+            token: "BTC".to_string(),
+            balance: 100,
+            memo: String::new(),
+            addr: "1BoatSLRHtKNngkdXEeobR76b53LETtpyT".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_rebuild() {
+        let events = vec![
+            (1, apply(0)),
+            (1, apply(1)),
+            (2, apply(2)),
+            (3, WithdrawalEvent::Proposal { ids: vec![0, 1, 2] }),
+            (4, WithdrawalEvent::DropProposal { ids: vec![0, 1, 2] }),
+            (5, WithdrawalEvent::Proposal { ids: vec![0, 1] }),
+            (
+                6,
+                WithdrawalEvent::BitcoinTx {
+                    id: 0,
+                    tx_hash: "0x01".to_string(),
+                    tx_state: TxState::Confirmed,
+                },
+            ),
+            (
+                6,
+                WithdrawalEvent::Finish {
+                    id: 0,
+                    state: ApplicationState::NormalFinish,
+                },
+            ),
+            (
+                7,
+                WithdrawalEvent::Finish {
+                    id: 3,
+                    state: ApplicationState::NormalCancel,
+                },
+            ),
+            // The finished withdrawal stays finished.
+            (8, WithdrawalEvent::Proposal { ids: vec![0] }),
+        ];
+        let withdrawals = rebuild(block_records(events));
+        assert_eq!(withdrawals.len(), 3);
+        assert_eq!(withdrawals[0].state, ApplicationState::NormalFinish);
+        assert_eq!(withdrawals[0].finish_height, Some(6));
+        assert_eq!(withdrawals[0].tx_state, Some(TxState::Confirmed));
+        assert!(!withdrawals[0].pending);
+        assert_eq!(withdrawals[1].state, ApplicationState::Processing);
+        assert!(withdrawals[1].pending);
+        assert_eq!(withdrawals[2].height, 2);
+        assert_eq!(withdrawals[2].state, ApplicationState::Applying);
+        assert!(withdrawals[2].pending);
+    }
+}
@@ -2,9 +2,11 @@
 
 mod chainx;
 mod cmd;
+mod export;
 
 pub use self::chainx::*;
-pub use self::cmd::{CmdConfig, Config};
+pub use self::cmd::{CmdConfig, Command, Config, ExportTarget, VerifyTarget};
+pub use self::export::{retry_missing, run_stages, Stage};
 
 use std::{env, fs, io::Write};
 
@@ -20,6 +22,68 @@ pub fn log_missing_block_height(height: u64) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Log the events of the block that were skipped while scanning the accounts, as
+/// `[height] skipped [indices]` in `accounts/missing.log`.
+pub fn log_skipped_events(height: u64, skipped: &[u32]) -> anyhow::Result<()> {
+    let mut dir = env::current_dir()?;
+    dir.push("accounts");
+    fs::create_dir_all(dir.as_path())?;
+    let mut file = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(format!("{}/missing.log", dir.display()))?;
+    file.write_all(format!("{} skipped {:?}\n", height, skipped).as_bytes())?;
+    Ok(())
+}
+
+/// The block heights in `accounts/missing.log`, deduplicated and sorted.
+pub fn load_missing_block_heights() -> anyhow::Result<Vec<u64>> {
+    let mut path = env::current_dir()?;
+    path.push("accounts");
+    path.push("missing.log");
+    if fs::metadata(path.as_path()).is_err() {
+        return Ok(vec![]);
+    }
+    let mut heights = fs::read_to_string(path)?
+        .lines()
+        .map(str::trim)
+        .filter_map(|line| line.split_whitespace().next())
+        .map(|height| height.parse::<u64>())
+        .collect::<Result<Vec<_>, _>>()?;
+    heights.sort_unstable();
+    heights.dedup();
+    Ok(heights)
+}
+
+/// Replace `accounts/missing.log` with the given block heights.
+pub fn save_missing_block_heights(heights: &[u64]) -> anyhow::Result<()> {
+    let mut dir = env::current_dir()?;
+    dir.push("accounts");
+    fs::create_dir_all(dir.as_path())?;
+    let content = heights
+        .iter()
+        .map(|height| format!("{}\n", height))
+        .collect::<String>();
+    fs::write(format!("{}/missing.log", dir.display()), content)?;
+    Ok(())
+}
+
+/// The names of all files in `accounts/`.
+pub fn accounts_filenames() -> anyhow::Result<Vec<String>> {
+    let mut dir = env::current_dir()?;
+    dir.push("accounts");
+    if fs::metadata(dir.as_path()).is_err() {
+        return Ok(vec![]);
+    }
+    let mut filenames = vec![];
+    for entry in fs::read_dir(dir)? {
+        if let Some(filename) = entry?.file_name().to_str() {
+            filenames.push(filename.to_string());
+        }
+    }
+    Ok(filenames)
+}
+
 pub fn accounts_exists<S: AsRef<str>>(filename: S) -> anyhow::Result<bool> {
     let mut path = env::current_dir()?;
     path.push("accounts");
@@ -46,6 +110,7 @@ where
     let file = fs::OpenOptions::new()
         .create(true)
         .write(true)
+        .truncate(true)
         .open(format!("{}/{}", dir.display(), filename.as_ref()))?;
     Ok(serde_json::to_writer_pretty(file, value)?)
 }
@@ -77,6 +142,7 @@ where
     let file = fs::OpenOptions::new()
         .create(true)
         .write(true)
+        .truncate(true)
         .open(format!("{}/{}", dir.display(), filename.as_ref()))?;
     Ok(serde_json::to_writer_pretty(file, value)?)
 }
@@ -96,3 +162,43 @@ where
     ))?;
     Ok(serde_json::from_reader(file)?)
 }
+
+/// Whether the file of the archive (like `extrinsics`) exists.
+pub fn archive_exists<S: AsRef<str>>(archive: &str, filename: S) -> anyhow::Result<bool> {
+    let mut path = env::current_dir()?;
+    path.push(archive);
+    path.push(filename.as_ref());
+    Ok(fs::metadata(path).is_ok())
+}
+
+/// Save the file of the archive in the compact JSON, since the archives are large.
+pub fn save_archive<S, T>(archive: &str, filename: S, value: &T) -> anyhow::Result<()>
+where
+    S: AsRef<str>,
+    T: ?Sized + serde::Serialize,
+{
+    let mut dir = env::current_dir()?;
+    dir.push(archive);
+    fs::create_dir_all(dir.as_path())?;
+    let file = fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .truncate(true)
+        .open(format!("{}/{}", dir.display(), filename.as_ref()))?;
+    Ok(serde_json::to_writer(file, value)?)
+}
+
+pub fn load_archive<S, T>(archive: &str, filename: S) -> anyhow::Result<T>
+where
+    S: AsRef<str>,
+    T: serde::de::DeserializeOwned,
+{
+    let mut dir = env::current_dir()?;
+    dir.push(archive);
+    let file = fs::OpenOptions::new().read(true).open(format!(
+        "{}/{}",
+        dir.display(),
+        filename.as_ref()
+    ))?;
+    Ok(serde_json::from_reader(std::io::BufReader::new(file))?)
+}
@@ -0,0 +1,15 @@
+// Copyright 2019-2020 ChainX Project Authors. Licensed under GPL-3.0.
+
+use anyhow::Result;
+use chainx_state_exporter::*;
+
+#[async_std::main]
+async fn main() -> Result<()> {
+    env_logger::init();
+
+    let (command, conf) = CmdConfig::init()?;
+    match command {
+        Command::RetryMissing => retry_missing(&conf).await,
+        _ => run_stages(&conf, &command.stages(), command.force()).await,
+    }
+}